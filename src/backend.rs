@@ -11,21 +11,48 @@ const DOWNLOAD_BATCH_SIZE: usize = 1000;
 /// Batch size for progress callbacks during message fetching (independent from indexing batches)
 const FETCH_PROGRESS_BATCH_SIZE: usize = 100;
 
-use crate::config::BackendConfig;
-use crate::indexer::Indexer;
-use crate::session::ClientSession;
-use crate::types::{DownloadProgress, IndexMsg, Result, SearchResult};
-use crate::utils::{brief_content, escape_content, get_share_id};
+/// Shared rate limiter budget for [`BackendBot::download_histories`]: a
+/// burst of at most this many history requests, refilled at this rate, so
+/// several concurrent chat downloads stay under Telegram's account-wide
+/// limit instead of each pacing itself independently.
+const DOWNLOAD_RATE_LIMIT_CAPACITY: f64 = 10.0;
+const DOWNLOAD_RATE_LIMIT_PER_SEC: f64 = 5.0;
+
+/// How long a negative `str_to_chat_id` username lookup is cached before
+/// it's retried against the API, so repeated bad input (a typo, a chat
+/// that hasn't been created yet) is throttled rather than cached forever.
+const NEGATIVE_USERNAME_CACHE_TTL: Duration = Duration::from_secs(300);
+
+use crate::chat_config::{ChatConfig, ChatConfigStore, ChatFlag, InMemChatConfigStore};
+use crate::config::{BackendBotConfig, BackendConfig, UrlNormalizeConfig};
+use crate::indexer::{Indexer, MatchMode};
+use crate::link_enrich::{InMemLinkCacheStore, LinkCacheStore};
+use crate::metrics::Metrics;
+use crate::msg_chat_map::{InMemMsgChatMapStore, MsgChatMapStore};
+use crate::ratelimit::TokenBucket;
+use crate::session::{ClientSession, UsernameCacheEntry};
+use crate::sinks::{EventSink, IndexEvent, IndexEventKind, build_sinks};
+use crate::types::{DownloadOutcome, DownloadProgress, IndexMsg, MediaType, Result, SearchResult};
+use crate::utils::{
+    PeerType, brief_content, build_message_key, escape_content, get_share_id, resolve_id,
+};
 use dashmap::DashMap;
 use grammers_client::Client;
 use grammers_client::client::UpdatesConfiguration;
 use grammers_client::types::update::Message as UpdateMessage; // Update message type
 use grammers_client::types::update::{MessageDeletion, Update};
 use grammers_mtsender::{ConnectionParams, SenderPool};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Semaphore;
 use tracing::{debug, error, info, warn};
 
+/// Maximum number of consecutive `FLOOD_WAIT` retries during history
+/// download before giving up. Mirrors `BotFrontend`'s retry bound for
+/// message send/edit RPCs.
+const MAX_FLOOD_WAIT_RETRIES: u32 = 5;
+
 /// Backend bot for indexing messages
 pub struct BackendBot {
     /// Backend ID
@@ -40,32 +67,99 @@ pub struct BackendBot {
     /// Chat ID to name cache (shared from session)
     chat_cache: Arc<DashMap<i64, String>>,
 
+    /// Username to chat ID cache (shared from session, see
+    /// [`Self::str_to_chat_id`])
+    username_cache: Arc<DashMap<String, UsernameCacheEntry>>,
+
+    /// Reverse of `username_cache`: the last username seen for each chat
+    /// ID, so [`Self::refresh_chat_names_async`] can tell a chat's
+    /// `@username` changed and invalidate the stale entry instead of
+    /// leaving it in `username_cache` forever.
+    chat_username: Arc<DashMap<i64, String>>,
+
     /// Search indexer
     indexer: Arc<Indexer>,
 
     /// Set of chat IDs being monitored
     monitored_chats: Arc<DashMap<i64, ()>>,
 
-    /// Set of chat IDs excluded from monitoring
-    excluded_chats: HashSet<i64>,
+    /// Set of chat IDs excluded from monitoring. Wrapped so a config reload
+    /// (see `apply_config`) can swap it live without restarting the backend.
+    excluded_chats: std::sync::RwLock<HashSet<i64>>,
+
+    /// Per-chat indexing/search configuration (persisted across restarts)
+    chat_config: Arc<dyn ChatConfigStore>,
+
+    /// Reverse `msg_id -> share_id` lookup, populated as messages are
+    /// indexed, so a bare `MessageDeletion` with no `channel_id` (private
+    /// chats, basic groups) can still be resolved (see
+    /// `handle_message_deleted`).
+    msg_chat_map: Arc<dyn MsgChatMapStore>,
+
+    /// Cache of `url -> enrichment text` consulted by [`Self::enrich_content`]
+    /// (see [`crate::link_enrich`]), shared across chats so the same link is
+    /// only ever fetched once.
+    link_cache: Arc<dyn LinkCacheStore>,
+
+    /// Whether to append fetched link titles/descriptions to indexed
+    /// content (see [`Self::enrich_content`]). Set once from
+    /// `BackendBotConfig::enrich_links` at construction; `new` rejects this
+    /// being `true` when the binary wasn't built with the `link-enrich`
+    /// feature.
+    enrich_links: bool,
+
+    /// AMP-mirror hosts and tracking query parameters to normalize out of
+    /// indexed content (see [`Self::normalize_links`] and
+    /// `crate::url_normalize`).
+    url_normalize: UrlNormalizeConfig,
 
     /// Track newest message per chat
     newest_msg: Arc<DashMap<i64, IndexMsg>>,
 
-    /// Configuration
-    monitor_all: bool,
+    /// Whether to monitor all chats except `excluded_chats`. Wrapped for the
+    /// same reason as `excluded_chats`.
+    monitor_all: std::sync::RwLock<bool>,
+
+    /// Signaled to stop `run()`'s event loop for a graceful shutdown; shared
+    /// with the supervisor task that owns this backend (see
+    /// `crate::supervisor`).
+    shutdown: Arc<tokio::sync::Notify>,
+
+    /// Indexing-throughput and event-loop-error counters reported on the
+    /// `/metrics` endpoint (see `crate::metrics`).
+    metrics: Metrics,
+
+    /// Downstream sinks indexing events are fanned out to after a
+    /// successful indexer write (see `crate::sinks`).
+    sinks: Vec<Box<dyn EventSink>>,
 }
 
 impl BackendBot {
-    /// Create a new backend bot
+    /// Create a new backend bot. `shutdown` is notified by the supervisor to
+    /// stop `run()`'s event loop in place of killing the task. `metrics` is
+    /// shared with every other backend/frontend and the `/metrics` endpoint.
     pub async fn new(
         backend_id: &str,
         config: &BackendConfig,
         session: Arc<ClientSession>,
         indexer: Arc<Indexer>,
+        shutdown: Arc<tokio::sync::Notify>,
+        metrics: Metrics,
     ) -> Result<Self> {
         info!("Creating backend bot: {}", backend_id);
 
+        if config.config.enrich_links && !cfg!(feature = "link-enrich") {
+            return Err(crate::types::Error::Config(
+                "'enrich_links' is enabled but this binary was built without the \
+                 'link-enrich' feature"
+                    .to_string(),
+            ));
+        }
+
+        metrics.register_indexer(backend_id, indexer.clone());
+
+        let sinks = build_sinks(&config.config.sinks).await?;
+
         // Get all indexed chats to monitor (doesn't require a client)
         let indexed_chats = indexer.list_indexed_chats().await?;
         let monitored_chats = Arc::new(DashMap::new());
@@ -86,14 +180,108 @@ impl BackendBot {
             session: session.clone(),
             client: std::sync::OnceLock::new(),
             chat_cache: session.chat_cache(),
+            username_cache: session.username_cache(),
+            chat_username: Arc::new(DashMap::new()),
             indexer,
             monitored_chats,
-            excluded_chats,
+            excluded_chats: std::sync::RwLock::new(excluded_chats),
             newest_msg: Arc::new(DashMap::new()),
-            monitor_all: config.config.monitor_all,
+            monitor_all: std::sync::RwLock::new(config.config.monitor_all),
+            chat_config: Arc::new(InMemChatConfigStore::new()),
+            msg_chat_map: Arc::new(InMemMsgChatMapStore::new()),
+            link_cache: Arc::new(InMemLinkCacheStore::new()),
+            enrich_links: config.config.enrich_links,
+            url_normalize: config.config.url_normalize.clone(),
+            shutdown,
+            metrics,
+            sinks,
         })
     }
 
+    /// Fan an indexing event out to every configured sink. Each sink's
+    /// filter is checked inside its own `publish`; a sink failure is logged
+    /// and never propagated, so one bad webhook can't stall indexing.
+    async fn publish_event(&self, kind: IndexEventKind, msg: IndexMsg) {
+        if self.sinks.is_empty() {
+            return;
+        }
+        let event = IndexEvent { kind, msg };
+        for sink in &self.sinks {
+            if let Err(e) = sink.publish(&event).await {
+                warn!("Backend '{}': sink publish failed: {}", self.id, e);
+            }
+        }
+    }
+
+    /// Apply a reloaded [`BackendBotConfig`] live, without restarting the
+    /// backend or its session, by swapping in its `monitor_all` and
+    /// `excluded_chats`. The other config-derived fields (`sinks`,
+    /// `enrich_links`, `url_normalize`) are not updated and keep the values
+    /// they were given in [`Self::new`].
+    pub fn apply_config(&self, config: &BackendBotConfig) {
+        let excluded_chats: HashSet<i64> = config
+            .excluded_chats
+            .iter()
+            .map(|&id| get_share_id(id))
+            .collect();
+        *self.monitor_all.write().unwrap() = config.monitor_all;
+        *self.excluded_chats.write().unwrap() = excluded_chats;
+        info!(
+            "Backend '{}': applied reloaded monitor_all/excluded_chats config",
+            self.id
+        );
+    }
+
+    /// Replace the per-chat configuration store (e.g. with a persistent
+    /// SQLite-backed one). Defaults to [`InMemChatConfigStore`] after
+    /// [`BackendBot::new`].
+    pub fn set_chat_config_store(&mut self, store: Arc<dyn ChatConfigStore>) {
+        self.chat_config = store;
+    }
+
+    /// Replace the reverse `msg_id -> share_id` lookup store (e.g. with a
+    /// persistent SQLite-backed one). Defaults to [`InMemMsgChatMapStore`]
+    /// after [`BackendBot::new`].
+    pub fn set_msg_chat_map_store(&mut self, store: Arc<dyn MsgChatMapStore>) {
+        self.msg_chat_map = store;
+    }
+
+    /// Replace the link-enrichment cache store (e.g. with a persistent
+    /// SQLite-backed one). Defaults to [`InMemLinkCacheStore`] after
+    /// [`BackendBot::new`].
+    pub fn set_link_cache_store(&mut self, store: Arc<dyn LinkCacheStore>) {
+        self.link_cache = store;
+    }
+
+    /// Resolve the effective configuration for a chat, falling back to
+    /// [`ChatConfig::default_for`] for chats the admin has not configured.
+    pub async fn chat_config(&self, share_id: i64) -> ChatConfig {
+        let share_id = get_share_id(share_id);
+        match self.chat_config.get(share_id).await {
+            Ok(Some(cfg)) => cfg,
+            Ok(None) => ChatConfig::default_for(share_id),
+            Err(e) => {
+                warn!("Failed to read chat config for {}: {}", share_id, e);
+                ChatConfig::default_for(share_id)
+            }
+        }
+    }
+
+    /// Set a single boolean flag on a chat, persisting it through the store.
+    /// Used by the `/monitor_chat` and `/unmonitor_chat` admin commands.
+    pub async fn set_chat_flag(&self, share_id: i64, flag: ChatFlag, value: bool) -> Result<()> {
+        let share_id = get_share_id(share_id);
+        self.chat_config.set_flag(share_id, flag, value).await?;
+        if flag == ChatFlag::IndexingEnabled {
+            if value {
+                self.monitored_chats.insert(share_id, ());
+            } else {
+                self.monitored_chats.remove(&share_id);
+            }
+        }
+        Ok(())
+    }
+
     /// Initialize backend and validate monitored chats
     pub async fn initialize(&self) -> Result<()> {
         info!("Initializing backend bot: {}", self.id);
@@ -132,58 +320,88 @@ impl BackendBot {
             }
         }
 
+        if let Some(offset) = self.session.load_update_offset() {
+            info!(
+                "Backend '{}' resuming update stream (last processed offset {})",
+                self.id, offset
+            );
+        }
+
         let mut updates = updates_client.stream_updates(
             updates,
             UpdatesConfiguration {
-                catch_up: false, // Don't fetch old updates - only receive new ones from now
+                // Replay anything missed while we were offline/reconnecting.
+                // Grammers persists the detailed pts/qts/seq/date state in its
+                // own session DB and replays the gap via getDifference.
+                catch_up: true,
                 ..Default::default()
             },
         );
 
         info!("Backend streaming updates, waiting for messages...");
         loop {
-            match updates.next().await {
-                Ok(update) => {
+            tokio::select! {
+                update = updates.next() => {
                     match update {
-                        Update::NewMessage(message) => {
-                            let chat_id = message.peer_id().bot_api_dialog_id();
-                            let share_id = crate::utils::get_share_id(chat_id);
-                            debug!("Backend received new message from chat {}", share_id);
-                            if let Err(e) = self.handle_new_message(message).await {
-                                error!("Error handling new message: {}", e);
-                            }
-                        }
-                        Update::MessageEdited(message) => {
-                            let chat_id = message.peer_id().bot_api_dialog_id();
-                            let share_id = crate::utils::get_share_id(chat_id);
-                            debug!("Backend received edited message from chat {}", share_id);
-                            if let Err(e) = self.handle_message_edited(message).await {
-                                error!("Error handling edited message: {}", e);
+                        Ok(update) => {
+                            match update {
+                                Update::NewMessage(message) => {
+                                    let chat_id = message.peer_id().bot_api_dialog_id();
+                                    let share_id = crate::utils::get_share_id(chat_id);
+                                    debug!("Backend received new message from chat {}", share_id);
+                                    if let Err(e) = self.handle_new_message(message).await {
+                                        error!("Error handling new message: {}", e);
+                                    }
+                                }
+                                Update::MessageEdited(message) => {
+                                    let chat_id = message.peer_id().bot_api_dialog_id();
+                                    let share_id = crate::utils::get_share_id(chat_id);
+                                    debug!("Backend received edited message from chat {}", share_id);
+                                    if let Err(e) = self.handle_message_edited(message).await {
+                                        error!("Error handling edited message: {}", e);
+                                    }
+                                }
+                                Update::MessageDeleted(deletion) => {
+                                    debug!("Backend received message deletion");
+                                    if let Err(e) = self.handle_message_deleted(deletion).await {
+                                        error!("Error handling deleted message: {}", e);
+                                    }
+                                }
+                                _ => {
+                                    // Log other update types at debug level
+                                    debug!(
+                                        "Backend received other update: {:?}",
+                                        std::any::type_name_of_val(&update)
+                                    );
+                                }
                             }
+                            // Coarse diagnostic counter only; the real replay
+                            // state lives in grammers' session DB (see
+                            // `ClientSession::load_update_offset`).
+                            self.session.persist_update_offset();
                         }
-                        Update::MessageDeleted(deletion) => {
-                            debug!("Backend received message deletion");
-                            if let Err(e) = self.handle_message_deleted(deletion).await {
-                                error!("Error handling deleted message: {}", e);
-                            }
-                        }
-                        _ => {
-                            // Log other update types at debug level
-                            debug!(
-                                "Backend received other update: {:?}",
-                                std::any::type_name_of_val(&update)
-                            );
+                        Err(e) => {
+                            // Propagate the error so the supervisor restarts us
+                            // with backoff instead of leaving the backend dead.
+                            self.metrics.record_event_loop_error(&self.id);
+                            return Err(crate::types::Error::Telegram(format!(
+                                "Update stream error: {}",
+                                e
+                            )));
                         }
                     }
                 }
-                Err(e) => {
-                    error!("Error getting update: {}", e);
-                    // Break on error - will cause backend task to exit
+                _ = self.shutdown.notified() => {
+                    info!("Backend '{}' received shutdown signal", self.id);
                     break;
                 }
             }
         }
 
+        if let Err(e) = self.indexer.flush().await {
+            warn!("Backend '{}': failed to flush index on shutdown: {}", self.id, e);
+        }
+
         warn!("Backend '{}' event loop exited", self.id);
         Ok(())
     }
@@ -195,8 +413,50 @@ impl BackendBot {
         chats: Option<&[i64]>,
         page_len: usize,
         page_num: usize,
+        match_mode: MatchMode,
     ) -> Result<SearchResult> {
-        self.indexer.search(query, chats, page_len, page_num).await
+        // When the caller scopes the search to specific chats, drop any whose
+        // per-chat config marks them non-searchable.
+        let filtered: Option<Vec<i64>> = match chats {
+            Some(chats) => {
+                let mut visible = Vec::new();
+                for &chat_id in chats {
+                    if self.chat_config(chat_id).await.searchable {
+                        visible.push(chat_id);
+                    }
+                }
+                Some(visible)
+            }
+            None => {
+                // A global search must still hide chats marked non-searchable.
+                // Only pay for a scan when at least one such chat exists; scope
+                // the search to the searchable subset of indexed chats.
+                let blocked: HashSet<i64> = self
+                    .chat_config
+                    .all()
+                    .await
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|c| !c.searchable)
+                    .map(|c| c.share_id)
+                    .collect();
+                if blocked.is_empty() {
+                    None
+                } else {
+                    let visible: Vec<i64> = self
+                        .indexer
+                        .list_indexed_chats()
+                        .await?
+                        .into_iter()
+                        .filter(|id| !blocked.contains(id))
+                        .collect();
+                    Some(visible)
+                }
+            }
+        };
+        self.indexer
+            .search(query, filtered.as_deref(), page_len, page_num, match_mode)
+            .await
     }
 
     /// Get a random message
@@ -204,6 +464,116 @@ impl BackendBot {
         self.indexer.retrieve_random_document().await
     }
 
+    /// Compact the index by merging its segments into one, for an admin
+    /// command (`/optimize`) to run periodically. See
+    /// `Indexer::optimize` for why this helps.
+    pub async fn optimize_index(&self) -> Result<()> {
+        self.indexer.optimize().await
+    }
+
+    /// Commit any writes still buffered by the index's debounced writer
+    /// actor, so a graceful shutdown doesn't drop the last few seconds of
+    /// edits. Called once per backend as part of process shutdown.
+    pub async fn flush_index(&self) -> Result<()> {
+        self.indexer.flush().await
+    }
+
+    /// Re-fetch a chat's full history from Telegram and atomically replace
+    /// its indexed documents, for an admin command (`/rebuild_chat`) to
+    /// repair a chat's entries without restarting the whole backend. Unlike
+    /// [`Self::download_history`], which only adds messages, this discards
+    /// anything currently indexed for the chat that the fresh fetch doesn't
+    /// turn up (e.g. leftovers from an earlier partial or corrupted
+    /// download), via [`crate::indexer::Indexer::rebuild_chat`].
+    pub async fn rebuild_chat(&self, chat_id: i64) -> Result<DownloadOutcome> {
+        let (share_id, peer_type) = crate::utils::resolve_id(chat_id);
+        info!("Rebuilding chat {} from scratch", share_id);
+
+        let chat = self.find_peer_in_dialogs(share_id).await?.ok_or_else(|| {
+            crate::types::Error::EntityNotFound(format!(
+                "Chat {} not found in dialogs. Make sure you have access to this chat.",
+                share_id
+            ))
+        })?;
+
+        let client = self.get_client()?;
+        let mut message_iter = client.iter_messages(&chat);
+
+        let mut fetched_count = 0usize;
+        let mut indexed_count = 0usize;
+        let mut lowest_msg_id: Option<i32> = None;
+        let mut highest_msg_id: Option<i32> = None;
+        let mut messages: Vec<IndexMsg> = Vec::new();
+
+        let mut flood_retries = 0u32;
+        loop {
+            let message = match message_iter.next().await {
+                Ok(Some(message)) => message,
+                Ok(None) => break,
+                Err(e) => match crate::utils::flood_wait_secs(&e) {
+                    Some(secs) if flood_retries < MAX_FLOOD_WAIT_RETRIES => {
+                        flood_retries += 1;
+                        warn!(
+                            "FLOOD_WAIT({}) rebuilding chat {}, retrying in {}s ({}/{})",
+                            secs, share_id, secs, flood_retries, MAX_FLOOD_WAIT_RETRIES
+                        );
+                        tokio::time::sleep(Duration::from_secs(secs)).await;
+                        continue;
+                    }
+                    _ => {
+                        return Err(crate::types::Error::Telegram(format!(
+                            "Failed to iterate messages: {}",
+                            e
+                        )));
+                    }
+                },
+            };
+            flood_retries = 0;
+
+            let msg_id = message.id();
+            fetched_count += 1;
+            lowest_msg_id = Some(lowest_msg_id.map_or(msg_id, |l| l.min(msg_id)));
+            highest_msg_id = Some(highest_msg_id.map_or(msg_id, |h| h.max(msg_id)));
+
+            let text = message.text();
+            let (content, media_type) = self.extract_content(text, message.media());
+            if let Some(content) = content {
+                let content = self.normalize_links(content);
+                let content = self.enrich_content(content).await;
+                let sender = message
+                    .sender()
+                    .and_then(|p| p.name())
+                    .unwrap_or("Unknown")
+                    .to_string();
+
+                self.msg_chat_map.record(msg_id, share_id).await?;
+
+                messages.push(IndexMsg {
+                    content,
+                    url: build_message_key(peer_type, share_id, msg_id),
+                    chat_id: share_id,
+                    post_time: message.date(),
+                    sender,
+                    media_type,
+                });
+                indexed_count += 1;
+            }
+        }
+
+        self.indexer.rebuild_chat(share_id, messages).await?;
+        info!(
+            "Rebuilt chat {}: fetched {}, indexed {}",
+            share_id, fetched_count, indexed_count
+        );
+
+        Ok(DownloadOutcome {
+            fetched: fetched_count,
+            indexed: indexed_count,
+            lowest_msg_id: lowest_msg_id.unwrap_or(0),
+            highest_msg_id: highest_msg_id.unwrap_or(0),
+        })
+    }
+
     /// Get the client, returning an error if not initialized
     fn get_client(&self) -> Result<&Client> {
         self.client.get().ok_or_else(|| {
@@ -217,7 +587,10 @@ impl BackendBot {
     pub async fn is_empty(&self, chat_id: Option<i64>) -> Result<bool> {
         if let Some(chat_id) = chat_id {
             // Check if specific chat has any documents
-            let results = self.indexer.search("*", Some(&[chat_id]), 1, 1).await?;
+            let results = self
+                .indexer
+                .search("*", Some(&[chat_id]), 1, 1, MatchMode::Exact)
+                .await?;
             Ok(results.total_results == 0)
         } else {
             // Check if entire index is empty
@@ -249,6 +622,37 @@ impl BackendBot {
         Ok(None)
     }
 
+    /// Whether `user_id` is a creator/administrator of `chat_id`.
+    ///
+    /// Used to gate per-chat commands (e.g. `/monitor`, `/unmonitor`) so any
+    /// chat member can't toggle indexing for the whole chat. Returns `false`
+    /// (rather than erroring) if the chat can't be found in dialogs.
+    pub async fn is_chat_admin(&self, chat_id: i64, user_id: i64) -> Result<bool> {
+        let share_id = get_share_id(chat_id);
+        let peer = match self.find_peer_in_dialogs(share_id).await? {
+            Some(peer) => peer,
+            None => return Ok(false),
+        };
+
+        let client = self.get_client()?;
+        let mut participants = client.iter_participants(&peer);
+        while let Some(participant) = participants.next().await.map_err(|e| {
+            crate::types::Error::Telegram(format!("Failed to list chat admins: {}", e))
+        })? {
+            if participant.user.id() == user_id
+                && matches!(
+                    participant.role(),
+                    grammers_client::types::ParticipantRole::Creator
+                        | grammers_client::types::ParticipantRole::Admin
+                )
+            {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+
     /// Download chat history and index it
     ///
     /// The `progress_callback` is called while fetching messages with progress information.
@@ -259,7 +663,86 @@ impl BackendBot {
         min_id: Option<i32>,
         max_id: Option<i32>,
         progress_callback: Option<F>,
-    ) -> Result<usize>
+    ) -> Result<DownloadOutcome>
+    where
+        F: Fn(DownloadProgress),
+    {
+        self.download_history_impl(chat_id, min_id, max_id, progress_callback, None)
+            .await
+    }
+
+    /// Download chat history for several chats concurrently, sharing a single
+    /// token-bucket rate limiter so the combined request rate stays under
+    /// Telegram's account-wide limit and a `FLOOD_WAIT` hit by any one chat's
+    /// fetch pauses requests for all of them.
+    ///
+    /// `concurrency` bounds how many chats are fetched at once; the return
+    /// maps each requested `chat_id` to its own [`DownloadOutcome`] (or the
+    /// error that chat's download failed with).
+    pub async fn download_histories<F>(
+        self: &Arc<Self>,
+        chat_ids: &[i64],
+        concurrency: usize,
+        min_id: Option<i32>,
+        max_id: Option<i32>,
+        progress_callback: Option<F>,
+    ) -> HashMap<i64, Result<DownloadOutcome>>
+    where
+        F: Fn(DownloadProgress) + Send + Sync + 'static,
+    {
+        let limiter = Arc::new(TokenBucket::new(
+            DOWNLOAD_RATE_LIMIT_CAPACITY,
+            DOWNLOAD_RATE_LIMIT_PER_SEC,
+        ));
+        let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+        let progress_callback = progress_callback.map(Arc::new);
+
+        let mut tasks = Vec::with_capacity(chat_ids.len());
+        for &chat_id in chat_ids {
+            let this = Arc::clone(self);
+            let limiter = Arc::clone(&limiter);
+            let semaphore = Arc::clone(&semaphore);
+            let callback = progress_callback
+                .clone()
+                .map(|cb| move |progress: DownloadProgress| cb(progress));
+            tasks.push(tokio::spawn(async move {
+                let _permit = semaphore
+                    .acquire_owned()
+                    .await
+                    .expect("download rate limit semaphore is never closed");
+                let outcome = this
+                    .download_history_impl(chat_id, min_id, max_id, callback, Some(limiter))
+                    .await;
+                (chat_id, outcome)
+            }));
+        }
+
+        let mut results = HashMap::with_capacity(tasks.len());
+        for task in tasks {
+            match task.await {
+                Ok((chat_id, outcome)) => {
+                    results.insert(chat_id, outcome);
+                }
+                Err(e) => {
+                    error!("download_histories: a download task panicked: {}", e);
+                }
+            }
+        }
+        results
+    }
+
+    /// Shared implementation behind [`Self::download_history`] and
+    /// [`Self::download_histories`]. `limiter`, when present, is acquired
+    /// before each message fetch and paused on `FLOOD_WAIT`, so several
+    /// concurrent callers can share one rate budget.
+    async fn download_history_impl<F>(
+        &self,
+        chat_id: i64,
+        min_id: Option<i32>,
+        max_id: Option<i32>,
+        progress_callback: Option<F>,
+        limiter: Option<Arc<TokenBucket>>,
+    ) -> Result<DownloadOutcome>
     where
         F: Fn(DownloadProgress),
     {
@@ -290,15 +773,46 @@ impl BackendBot {
         let mut newest: Option<IndexMsg> = None;
         let mut batch: Vec<IndexMsg> = Vec::new();
         let mut fetched_last_msg_id: i32 = 0;
+        // Track the span of message ids actually processed for checkpointing.
+        let mut lowest_msg_id: Option<i32> = None;
+        let mut highest_msg_id: Option<i32> = None;
 
         info!(
             "Downloading history from chat {} (streaming fetch + index)...",
             share_id
         );
 
-        while let Some(message) = message_iter.next().await.map_err(|e| {
-            crate::types::Error::Telegram(format!("Failed to iterate messages: {}", e))
-        })? {
+        let mut flood_retries = 0u32;
+        loop {
+            if let Some(ref limiter) = limiter {
+                limiter.acquire().await;
+            }
+            let message = match message_iter.next().await {
+                Ok(Some(message)) => message,
+                Ok(None) => break,
+                Err(e) => match crate::utils::flood_wait_secs(&e) {
+                    Some(secs) if flood_retries < MAX_FLOOD_WAIT_RETRIES => {
+                        flood_retries += 1;
+                        warn!(
+                            "FLOOD_WAIT({}) downloading history from {}, retrying in {}s ({}/{})",
+                            secs, share_id, secs, flood_retries, MAX_FLOOD_WAIT_RETRIES
+                        );
+                        if let Some(ref limiter) = limiter {
+                            limiter.pause_for(secs).await;
+                        }
+                        tokio::time::sleep(Duration::from_secs(secs)).await;
+                        continue;
+                    }
+                    _ => {
+                        return Err(crate::types::Error::Telegram(format!(
+                            "Failed to iterate messages: {}",
+                            e
+                        )));
+                    }
+                },
+            };
+            flood_retries = 0;
+
             let msg_id = message.id();
 
             // Check min/max bounds (iterator is newest -> oldest)
@@ -315,6 +829,8 @@ impl BackendBot {
 
             fetched_last_msg_id = msg_id;
             fetched_count += 1;
+            lowest_msg_id = Some(lowest_msg_id.map_or(msg_id, |l| l.min(msg_id)));
+            highest_msg_id = Some(highest_msg_id.map_or(msg_id, |h| h.max(msg_id)));
 
             if let Some(ref callback) = progress_callback
                 && fetched_count.is_multiple_of(FETCH_PROGRESS_BATCH_SIZE)
@@ -327,12 +843,16 @@ impl BackendBot {
                 });
             }
 
-            // Extract text and index if present
+            // Extract text and index if present (falling back to the media
+            // kind/file name for captionless attachments)
             let text = message.text();
-            if let Some(content) = self.extract_text(text) {
+            let (content, media_type) = self.extract_content(text, message.media());
+            if let Some(content) = content {
+                let content = self.normalize_links(content);
+                let content = self.enrich_content(content).await;
                 // Create IndexMsg from iter_messages result
                 let chat_id = message.peer_id().bot_api_dialog_id();
-                let share_id = get_share_id(chat_id);
+                let (share_id, peer_type) = resolve_id(chat_id);
                 let sender = message
                     .sender()
                     .and_then(|p| p.name())
@@ -340,12 +860,15 @@ impl BackendBot {
                     .to_string();
                 let post_time = message.date();
 
+                self.msg_chat_map.record(msg_id, share_id).await?;
+
                 let index_msg = IndexMsg {
                     content,
-                    url: format!("https://t.me/c/{}/{}", share_id, msg_id),
+                    url: build_message_key(peer_type, share_id, msg_id),
                     chat_id: share_id,
                     post_time,
                     sender,
+                    media_type,
                 };
 
                 // Track newest (by post_time, independent of fetch order)
@@ -389,7 +912,18 @@ impl BackendBot {
             "Download complete for {}: fetched {}, indexed {}",
             share_id, fetched_count, indexed_count
         );
-        Ok(indexed_count)
+        Ok(DownloadOutcome {
+            fetched: fetched_count,
+            indexed: indexed_count,
+            lowest_msg_id: lowest_msg_id.unwrap_or(0),
+            highest_msg_id: highest_msg_id.unwrap_or(0),
+        })
+    }
+
+    /// Highest message id already indexed for a chat, if any. Thin wrapper
+    /// over the indexer used by incremental downloads.
+    pub async fn max_indexed_msg_id(&self, chat_id: i64) -> Result<Option<i32>> {
+        self.indexer.max_msg_id_for_chat(get_share_id(chat_id)).await
     }
 
     /// Clear index (optionally for specific chats)
@@ -438,25 +972,26 @@ impl BackendBot {
         Ok(cleared)
     }
 
-    /// Find chat IDs by name substring
+    /// Find chat IDs by a fuzzy, ranked match against cached chat names.
+    ///
+    /// Each cached name is scored with [`crate::utils::fuzzy_match_score`];
+    /// non-matching chats are dropped and the rest returned in descending
+    /// score order (ties broken by chat id for stable output).
     pub async fn find_chat_id(&self, query: &str) -> Result<Vec<i64>> {
-        let query_lower = query.to_lowercase();
-        let mut results = Vec::new();
+        let mut scored: Vec<(i64, i64)> = Vec::new();
 
         // Search in cache instead of iterating all dialogs
         for entry in self.chat_cache.iter() {
             let chat_id = *entry.key();
-            let chat_name = entry.value();
-
-            if chat_name.to_lowercase().contains(&query_lower) {
-                results.push(chat_id);
+            if let Some(score) = crate::utils::fuzzy_match_score(query, entry.value()) {
+                scored.push((chat_id, score));
             }
         }
 
-        // Sort by chat ID for consistency
-        results.sort();
+        // Highest score first; fall back to chat id for a stable ordering.
+        scored.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
 
-        Ok(results)
+        Ok(scored.into_iter().map(|(id, _)| id).collect())
     }
 
     /// Get cache entry count
@@ -476,6 +1011,8 @@ impl BackendBot {
             }
         };
         let chat_cache = Arc::clone(&self.chat_cache);
+        let username_cache = Arc::clone(&self.username_cache);
+        let chat_username = Arc::clone(&self.chat_username);
 
         tokio::spawn(async move {
             info!("Background: Refreshing chat name cache...");
@@ -491,6 +1028,20 @@ impl BackendBot {
                     chat_cache.insert(share_id, name.to_string());
                     count += 1;
                 }
+                // Re-resolving the chat here is also the natural point to
+                // invalidate a stale username cache entry: if this chat's
+                // `@username` changed since the last refresh, the old one
+                // would otherwise keep resolving to this chat forever, since
+                // `username_cache` only ever gets new entries inserted, not
+                // old ones removed.
+                if let Some(username) = peer.username() {
+                    if let Some(prev) = chat_username.insert(share_id, username.to_string())
+                        && prev != username
+                    {
+                        username_cache.remove(&prev);
+                    }
+                    username_cache.insert(username.to_string(), UsernameCacheEntry::Found(share_id));
+                }
             }
 
             info!("Background: Refreshed {} chat names in cache", count);
@@ -515,16 +1066,14 @@ impl BackendBot {
         ));
         let mut cur_len = sb.len();
 
-        if self.monitor_all {
-            let excluded_msg = format!(
-                "{} chats excluded from indexing\n",
-                self.excluded_chats.len()
-            );
+        if *self.monitor_all.read().unwrap() {
+            let excluded: Vec<i64> = self.excluded_chats.read().unwrap().iter().copied().collect();
+            let excluded_msg = format!("{} chats excluded from indexing\n", excluded.len());
             if cur_len + excluded_msg.len() < length_limit - overflow_msg.len() {
                 sb.push_str(&excluded_msg);
                 cur_len += excluded_msg.len();
 
-                for &chat_id in &self.excluded_chats {
+                for &chat_id in &excluded {
                     let line = format!("- {}\n", self.format_dialog_html(chat_id).await?);
                     if cur_len + line.len() >= length_limit - overflow_msg.len() {
                         sb.push_str(overflow_msg);
@@ -588,12 +1137,26 @@ impl BackendBot {
     }
 
     /// Check if a chat should be monitored
-    fn should_monitor(&self, chat_id: i64) -> bool {
+    ///
+    /// The static config (`monitor_all` + `excluded_chats`) still sets the
+    /// baseline, but the per-chat [`ChatConfig`] overrides it at runtime: an
+    /// explicit `excluded` flag or a disabled `indexing_enabled` suppresses
+    /// indexing regardless of the global policy.
+    async fn should_monitor(&self, chat_id: i64) -> bool {
         let share_id = get_share_id(chat_id);
-        if self.monitor_all {
-            !self.excluded_chats.contains(&share_id)
+        let cfg = self.chat_config(share_id).await;
+        if cfg.excluded || !cfg.indexing_enabled {
+            return false;
+        }
+        // An explicit `/monitor_chat` (which inserts into `monitored_chats`)
+        // overrides the static policy, including the config-file exclude set.
+        if self.monitored_chats.contains_key(&share_id) {
+            return true;
+        }
+        if *self.monitor_all.read().unwrap() {
+            !self.excluded_chats.read().unwrap().contains(&share_id)
         } else {
-            self.monitored_chats.contains_key(&share_id)
+            false
         }
     }
 
@@ -607,10 +1170,154 @@ impl BackendBot {
         }
     }
 
-    /// Convert grammers UpdateMessage to IndexMsg
-    fn message_to_index_msg(&self, message: &UpdateMessage, content: String) -> Result<IndexMsg> {
+    /// Classify a message's media for the `media_type` index field, plus the
+    /// document/file name when there is one (used as the fallback content
+    /// below for captionless attachments).
+    fn classify_media(media: Option<grammers_client::types::Media>) -> (MediaType, Option<String>) {
+        use grammers_client::types::Media;
+        match media {
+            Some(Media::Photo(_)) => (MediaType::Photo, None),
+            Some(Media::Sticker(sticker)) => {
+                let name = sticker.document.name();
+                (MediaType::Sticker, (!name.is_empty()).then(|| name.to_string()))
+            }
+            Some(Media::Document(doc)) => {
+                let media_type = match doc.mime_type() {
+                    Some(mime) if mime.starts_with("video/") => MediaType::Video,
+                    Some(mime) if mime.starts_with("audio/") => MediaType::Audio,
+                    _ => MediaType::Document,
+                };
+                let name = doc.name();
+                (media_type, (!name.is_empty()).then(|| name.to_string()))
+            }
+            Some(_) => (MediaType::Document, None),
+            None => (MediaType::Text, None),
+        }
+    }
+
+    /// Build indexable content for a message: its (escaped) text/caption if
+    /// present, otherwise a label built from the media kind and file name so
+    /// captionless photos/videos/documents/stickers stay searchable by type
+    /// or name (e.g. "that PDF someone posted").
+    fn extract_content(
+        &self,
+        text: &str,
+        media: Option<grammers_client::types::Media>,
+    ) -> (Option<String>, MediaType) {
+        let (media_type, file_name) = Self::classify_media(media);
+        if let Some(content) = self.extract_text(text) {
+            return (Some(content), media_type);
+        }
+        if media_type == MediaType::Text {
+            return (None, media_type);
+        }
+        let label = match file_name {
+            Some(name) => format!("[{:?}] {}", media_type, escape_content(&name)),
+            None => format!("[{:?}]", media_type),
+        };
+        (Some(label), media_type)
+    }
+
+    /// Detect URLs in `content` and append each one's canonical form (see
+    /// `crate::url_normalize`) whenever normalization changes it — an AMP
+    /// mirror resolved to the real article, or a tracking-laden URL with
+    /// its query parameters stripped — so a message is found whether
+    /// someone searches the original link or the canonical one. Always
+    /// runs (unlike [`Self::enrich_content`], it has no config flag of its
+    /// own): the built-in rules in `crate::url_normalize` apply even when
+    /// the configured lists are empty.
+    fn normalize_links(&self, mut content: String) -> String {
+        let urls = crate::link_enrich::extract_urls(&content);
+        if urls.is_empty() {
+            return content;
+        }
+
+        let canonicals: Vec<String> = urls
+            .iter()
+            .filter_map(|url| crate::url_normalize::normalize_url(url, &self.url_normalize).canonical)
+            .collect();
+
+        if !canonicals.is_empty() {
+            content.push_str("\n\n");
+            content.push_str(&canonicals.join("\n"));
+        }
+        content
+    }
+
+    /// If link enrichment is enabled, detect up to
+    /// [`crate::link_enrich::MAX_LINKS_PER_MESSAGE`] HTTP(S) URLs in
+    /// `content`, fetch (or reuse a cached) title/description for each, and
+    /// append the results so the message becomes findable by the linked
+    /// page's words. A no-op when `enrich_links` is off or `content` has no
+    /// links.
+    async fn enrich_content(&self, mut content: String) -> String {
+        if !self.enrich_links {
+            return content;
+        }
+        let urls = crate::link_enrich::extract_urls(&content);
+        if urls.is_empty() {
+            return content;
+        }
+
+        let mut enrichments = Vec::new();
+        for url in urls {
+            let info = match self.link_cache.get(&url).await {
+                Ok(Some(cached)) => cached,
+                Ok(None) => {
+                    let fetched = self.fetch_link(&url).await;
+                    if let Err(e) = self.link_cache.insert(&url, fetched.clone()).await {
+                        warn!("Backend '{}': failed to cache link enrichment for {}: {}", self.id, url, e);
+                    }
+                    fetched
+                }
+                Err(e) => {
+                    warn!("Backend '{}': failed to read link cache for {}: {}", self.id, url, e);
+                    None
+                }
+            };
+            if let Some(info) = info {
+                enrichments.push(info);
+            }
+        }
+
+        if !enrichments.is_empty() {
+            content.push_str("\n\n");
+            content.push_str(&enrichments.join("\n"));
+        }
+        content
+    }
+
+    /// Fetch a single URL's enrichment text through the Telegram-session
+    /// proxy, if any (see `create_sender_pool`). A no-op returning `None`
+    /// when built without the `link-enrich` feature; `enrich_links: true`
+    /// without that feature is already rejected in [`Self::new`].
+    #[cfg(feature = "link-enrich")]
+    async fn fetch_link(&self, url: &str) -> crate::link_enrich::LinkInfo {
+        let proxy_url = self.session.proxy().map(|s| s.as_str());
+        crate::link_enrich::fetch_enrichment(url, proxy_url)
+            .await
+            .unwrap_or_else(|e| {
+                warn!("Backend '{}': failed to fetch link enrichment for {}: {}", self.id, url, e);
+                None
+            })
+    }
+
+    #[cfg(not(feature = "link-enrich"))]
+    async fn fetch_link(&self, _url: &str) -> crate::link_enrich::LinkInfo {
+        None
+    }
+
+    /// Convert grammers UpdateMessage to IndexMsg, recording the
+    /// `msg_id -> share_id` pairing in [`Self::msg_chat_map`] along the way
+    /// so bare (non-channel) deletions can later be resolved.
+    async fn message_to_index_msg(
+        &self,
+        message: &UpdateMessage,
+        content: String,
+        media_type: MediaType,
+    ) -> Result<IndexMsg> {
         let chat_id = message.peer_id().bot_api_dialog_id();
-        let share_id = get_share_id(chat_id);
+        let (share_id, peer_type) = resolve_id(chat_id);
         let msg_id = message.id();
 
         // Get sender name from sender if available
@@ -623,12 +1330,15 @@ impl BackendBot {
         // Get post time
         let post_time = message.date();
 
+        self.msg_chat_map.record(msg_id, share_id).await?;
+
         Ok(IndexMsg {
             content,
-            url: format!("https://t.me/c/{}/{}", share_id, msg_id),
+            url: build_message_key(peer_type, share_id, msg_id),
             chat_id: share_id,
             post_time,
             sender,
+            media_type,
         })
     }
 
@@ -638,17 +1348,24 @@ impl BackendBot {
         let share_id = get_share_id(chat_id);
 
         // Check if we should monitor this chat
-        if !self.should_monitor(share_id) {
+        if !self.should_monitor(share_id).await {
             return Ok(());
         }
 
-        // Extract text
+        // Extract text (falling back to the media kind/file name for
+        // captionless attachments)
         let text = message.text();
-        if let Some(content) = self.extract_text(text) {
-            let index_msg = self.message_to_index_msg(&message, content.clone())?;
+        let (content, media_type) = self.extract_content(text, message.media());
+        if let Some(content) = content {
+            let content = self.normalize_links(content);
+            let content = self.enrich_content(content).await;
+            let index_msg = self.message_to_index_msg(&message, content.clone(), media_type).await?;
 
             // Add to index
             self.indexer.add_document(index_msg.clone()).await?;
+            self.metrics.record_indexed(&self.id);
+            self.publish_event(IndexEventKind::Created, index_msg.clone())
+                .await;
 
             // Update newest message
             self.newest_msg.insert(share_id, index_msg);
@@ -667,18 +1384,23 @@ impl BackendBot {
         let share_id = get_share_id(chat_id);
 
         // Check if we should monitor this chat
-        if !self.should_monitor(share_id) {
+        if !self.should_monitor(share_id).await {
             return Ok(());
         }
 
-        // Extract new text
+        // Extract new text (falling back to the media kind/file name for
+        // captionless attachments)
         let text = message.text();
-        if let Some(content) = self.extract_text(text) {
-            let msg_id = message.id();
-            let url = format!("https://t.me/c/{}/{}", share_id, msg_id);
+        let (content, media_type) = self.extract_content(text, message.media());
+        if let Some(content) = content {
+            let content = self.normalize_links(content);
+            let content = self.enrich_content(content).await;
 
             // Update in index
-            self.indexer.update_document(&url, &content).await?;
+            let index_msg = self.message_to_index_msg(&message, content.clone(), media_type).await?;
+            self.indexer.update_document(index_msg.clone()).await?;
+            self.metrics.record_edited(&self.id);
+            self.publish_event(IndexEventKind::Edited, index_msg).await;
 
             // Log with brief excerpt
             let brief = brief_content(&content, 20);
@@ -690,21 +1412,37 @@ impl BackendBot {
 
     /// Handle message deleted event
     async fn handle_message_deleted(&self, deletion: MessageDeletion) -> Result<()> {
-        // MessageDeletion only has channel_id for channels, not for regular chats
-        // For now, we'll need to track deletions differently or skip non-channel deletions
+        // MessageDeletion only carries a channel_id for channels; for private
+        // chats and basic groups we fall back to the reverse msg_chat_map
+        // lookup built up by message_to_index_msg/download_history.
         if let Some(channel_id) = deletion.channel_id() {
-            let share_id = get_share_id(channel_id);
+            let (share_id, peer_type) = resolve_id(channel_id);
 
             // Check if we should monitor this chat
-            if !self.should_monitor(share_id) {
+            if !self.should_monitor(share_id).await {
                 return Ok(());
             }
 
             // Delete each message from index
             for msg_id in deletion.messages() {
-                let url = format!("https://t.me/c/{}/{}", share_id, msg_id);
+                let url = build_message_key(peer_type, share_id, msg_id);
                 self.indexer.delete_document(&url).await?;
+                self.msg_chat_map.remove(msg_id, share_id).await?;
+                self.publish_event(
+                    IndexEventKind::Deleted,
+                    IndexMsg {
+                        content: String::new(),
+                        url,
+                        chat_id: share_id,
+                        post_time: chrono::Utc::now(),
+                        sender: String::new(),
+                        media_type: MediaType::Text,
+                    },
+                )
+                .await;
             }
+            self.metrics
+                .record_deleted(&self.id, deletion.messages().len() as u64);
 
             info!(
                 "Deleted {} messages from channel {}",
@@ -712,9 +1450,44 @@ impl BackendBot {
                 share_id
             );
         } else {
-            // For non-channel deletions, we can't determine which chat they're from
-            // This is a limitation of the Telegram API
-            warn!("Received deletion for non-channel chat, cannot process");
+            // Resolve each bare msg_id to the chat(s) it was indexed under,
+            // restricting to chats we currently monitor in case of id
+            // collisions across chats we don't track.
+            let mut deleted_count = 0u64;
+            for msg_id in deletion.messages() {
+                for share_id in self.msg_chat_map.lookup(msg_id).await? {
+                    if !self.should_monitor(share_id).await {
+                        continue;
+                    }
+                    // This branch only runs for deletions with no channel_id,
+                    // i.e. private chats and basic groups — `build_message_key`
+                    // treats both the same (no deep link), so the exact
+                    // non-`Channel` variant passed here doesn't matter.
+                    let url = build_message_key(PeerType::Chat, share_id, msg_id);
+                    self.indexer.delete_document(&url).await?;
+                    self.msg_chat_map.remove(msg_id, share_id).await?;
+                    deleted_count += 1;
+                    self.publish_event(
+                        IndexEventKind::Deleted,
+                        IndexMsg {
+                            content: String::new(),
+                            url,
+                            chat_id: share_id,
+                            post_time: chrono::Utc::now(),
+                            sender: String::new(),
+                            media_type: MediaType::Text,
+                        },
+                    )
+                    .await;
+                }
+            }
+            if deleted_count > 0 {
+                self.metrics.record_deleted(&self.id, deleted_count);
+            }
+            info!(
+                "Deleted {} messages from non-channel chat(s) via reverse lookup",
+                deleted_count
+            );
         }
 
         Ok(())
@@ -769,7 +1542,11 @@ impl BackendBot {
         Ok(format!("Chat_{}", chat_id))
     }
 
-    /// Resolve username or chat ID string to chat ID
+    /// Resolve username or chat ID string to chat ID. Successful
+    /// resolutions and `EntityNotFound` misses are both memoized in
+    /// [`Self::username_cache`] (positive entries until invalidated, negative
+    /// ones for [`NEGATIVE_USERNAME_CACHE_TTL`]) so repeated lookups of the
+    /// same username don't hit the API on every call.
     pub async fn str_to_chat_id(&self, s: &str) -> Result<i64> {
         // Try parsing as integer first
         if let Ok(id) = s.parse::<i64>() {
@@ -783,16 +1560,40 @@ impl BackendBot {
             .trim_start_matches("t.me/")
             .trim_start_matches('@');
 
+        if let Some(entry) = self.username_cache.get(username) {
+            match *entry {
+                UsernameCacheEntry::Found(share_id) => return Ok(share_id),
+                UsernameCacheEntry::NotFound(cached_at)
+                    if cached_at.elapsed() < NEGATIVE_USERNAME_CACHE_TTL =>
+                {
+                    return Err(crate::types::Error::EntityNotFound(username.to_string()));
+                }
+                UsernameCacheEntry::NotFound(_) => {} // expired; fall through and re-resolve
+            }
+        }
+
         // Resolve username
         let client = self.get_client()?;
-        let peer = client
-            .resolve_username(username)
-            .await
-            .map_err(|e| {
-                crate::types::Error::Telegram(format!("Failed to resolve username: {}", e))
-            })?
-            .ok_or_else(|| crate::types::Error::EntityNotFound(username.to_string()))?;
+        let resolved = client.resolve_username(username).await.map_err(|e| {
+            crate::types::Error::Telegram(format!("Failed to resolve username: {}", e))
+        })?;
+
+        let Some(peer) = resolved else {
+            self.username_cache
+                .insert(username.to_string(), UsernameCacheEntry::NotFound(Instant::now()));
+            return Err(crate::types::Error::EntityNotFound(username.to_string()));
+        };
+
+        let share_id = get_share_id(peer.id().bot_api_dialog_id());
+        self.username_cache
+            .insert(username.to_string(), UsernameCacheEntry::Found(share_id));
+        Ok(share_id)
+    }
 
-        Ok(get_share_id(peer.id().bot_api_dialog_id()))
+    /// Invalidate a cached username resolution (e.g. after a chat is
+    /// re-resolved and found to have moved/changed), forcing the next
+    /// [`Self::str_to_chat_id`] call for it back to the API.
+    pub fn invalidate_username(&self, username: &str) {
+        self.username_cache.remove(username);
     }
 }
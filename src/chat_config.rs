@@ -0,0 +1,302 @@
+//! Persistent, pluggable per-chat indexing/search configuration
+//!
+//! Historically the backend applied a single global policy (`monitor_all`
+//! plus a static `excluded_chats` set from the config file). Running the
+//! searcher over many dialogs calls for per-chat control instead: whether a
+//! chat is indexed, whether its messages show up in search, an optional
+//! display alias, and an explicit exclusion flag. This module mirrors the
+//! [`crate::chat_meta`] store: a backend-agnostic trait with an in-memory
+//! implementation preserving the old (all-default) behavior and a SQLite
+//! implementation persisted in the backend's `state.db` so `/monitor_chat`
+//! and `/unmonitor_chat` survive restarts.
+
+use crate::types::{Error, Result};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Per-chat configuration, keyed by the normalized `share_id`.
+#[derive(Debug, Clone)]
+pub struct ChatConfig {
+    /// Normalized share id of the chat.
+    pub share_id: i64,
+
+    /// Whether new messages from this chat are indexed.
+    pub indexing_enabled: bool,
+
+    /// Whether this chat's messages appear in search results.
+    pub searchable: bool,
+
+    /// Optional display name overriding the cached chat name.
+    pub alias: Option<String>,
+
+    /// Whether this chat is explicitly excluded from indexing.
+    pub excluded: bool,
+}
+
+impl ChatConfig {
+    /// Defaults for a chat the store has never seen: indexed and searchable,
+    /// no alias, not excluded. This keeps the pre-existing behavior for chats
+    /// an admin has not configured.
+    pub fn default_for(share_id: i64) -> Self {
+        Self {
+            share_id,
+            indexing_enabled: true,
+            searchable: true,
+            alias: None,
+            excluded: false,
+        }
+    }
+}
+
+/// A boolean flag on a [`ChatConfig`], used by [`ChatConfigStore::set_flag`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatFlag {
+    /// Toggle [`ChatConfig::indexing_enabled`].
+    IndexingEnabled,
+    /// Toggle [`ChatConfig::searchable`].
+    Searchable,
+    /// Toggle [`ChatConfig::excluded`].
+    Excluded,
+}
+
+/// Backend-agnostic store mapping `share_id -> ChatConfig`.
+#[async_trait]
+pub trait ChatConfigStore: Send + Sync {
+    /// Look up the stored configuration for a chat, if it has been configured.
+    async fn get(&self, share_id: i64) -> Result<Option<ChatConfig>>;
+
+    /// Set a single boolean `flag` on a chat, inserting a default-valued row
+    /// first for chats the store has not seen (entry-style upsert).
+    async fn set_flag(&self, share_id: i64, flag: ChatFlag, value: bool) -> Result<()>;
+
+    /// Set the display alias for a chat (clearing it with `None`).
+    #[allow(dead_code)]
+    async fn set_alias(&self, share_id: i64, alias: Option<String>) -> Result<()>;
+
+    /// Return a snapshot of every configured chat.
+    async fn all(&self) -> Result<Vec<ChatConfig>>;
+}
+
+/// In-memory store backed by a [`DashMap`].
+#[derive(Clone, Default)]
+pub struct InMemChatConfigStore {
+    data: Arc<DashMap<i64, ChatConfig>>,
+}
+
+impl InMemChatConfigStore {
+    /// Create an empty in-memory store.
+    pub fn new() -> Self {
+        Self {
+            data: Arc::new(DashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl ChatConfigStore for InMemChatConfigStore {
+    async fn get(&self, share_id: i64) -> Result<Option<ChatConfig>> {
+        Ok(self.data.get(&share_id).map(|v| v.clone()))
+    }
+
+    async fn set_flag(&self, share_id: i64, flag: ChatFlag, value: bool) -> Result<()> {
+        let mut cfg = self
+            .data
+            .entry(share_id)
+            .or_insert_with(|| ChatConfig::default_for(share_id));
+        apply_flag(&mut cfg, flag, value);
+        Ok(())
+    }
+
+    async fn set_alias(&self, share_id: i64, alias: Option<String>) -> Result<()> {
+        let mut cfg = self
+            .data
+            .entry(share_id)
+            .or_insert_with(|| ChatConfig::default_for(share_id));
+        cfg.alias = alias;
+        Ok(())
+    }
+
+    async fn all(&self) -> Result<Vec<ChatConfig>> {
+        Ok(self.data.iter().map(|e| e.value().clone()).collect())
+    }
+}
+
+/// Apply a boolean flag to an in-memory [`ChatConfig`].
+fn apply_flag(cfg: &mut ChatConfig, flag: ChatFlag, value: bool) {
+    match flag {
+        ChatFlag::IndexingEnabled => cfg.indexing_enabled = value,
+        ChatFlag::Searchable => cfg.searchable = value,
+        ChatFlag::Excluded => cfg.excluded = value,
+    }
+}
+
+/// SQLite-backed store in the database file given to [`Self::open`]
+/// (`main` uses the backend's `state.db` in its index directory).
+///
+/// The configuration lives in a dedicated `chat_config` table so it can be
+/// queried independently of grammers' own session state.
+pub struct SqliteChatConfigStore {
+    conn: tokio::sync::Mutex<rusqlite::Connection>,
+}
+
+impl SqliteChatConfigStore {
+    /// Open (creating if necessary) the config table in `db_path`.
+    pub fn open(db_path: &Path) -> Result<Self> {
+        let conn = rusqlite::Connection::open(db_path)
+            .map_err(|e| Error::Config(format!("Failed to open chat config db: {}", e)))?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS chat_config (
+                share_id         INTEGER PRIMARY KEY,
+                indexing_enabled INTEGER NOT NULL,
+                searchable       INTEGER NOT NULL,
+                alias            TEXT,
+                excluded         INTEGER NOT NULL
+            )",
+            [],
+        )
+        .map_err(|e| Error::Config(format!("Failed to create chat_config table: {}", e)))?;
+        Ok(Self {
+            conn: tokio::sync::Mutex::new(conn),
+        })
+    }
+
+    /// Column name backing a [`ChatFlag`].
+    fn flag_column(flag: ChatFlag) -> &'static str {
+        match flag {
+            ChatFlag::IndexingEnabled => "indexing_enabled",
+            ChatFlag::Searchable => "searchable",
+            ChatFlag::Excluded => "excluded",
+        }
+    }
+}
+
+#[async_trait]
+impl ChatConfigStore for SqliteChatConfigStore {
+    async fn get(&self, share_id: i64) -> Result<Option<ChatConfig>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn
+            .prepare(
+                "SELECT share_id, indexing_enabled, searchable, alias, excluded
+                 FROM chat_config WHERE share_id = ?1",
+            )
+            .map_err(|e| Error::Config(e.to_string()))?;
+        let row = stmt
+            .query_row([share_id], |row| {
+                Ok(ChatConfig {
+                    share_id: row.get::<_, i64>(0)?,
+                    indexing_enabled: row.get::<_, i64>(1)? != 0,
+                    searchable: row.get::<_, i64>(2)? != 0,
+                    alias: row.get::<_, Option<String>>(3)?,
+                    excluded: row.get::<_, i64>(4)? != 0,
+                })
+            })
+            .ok();
+        Ok(row)
+    }
+
+    async fn set_flag(&self, share_id: i64, flag: ChatFlag, value: bool) -> Result<()> {
+        let conn = self.conn.lock().await;
+        let default = ChatConfig::default_for(share_id);
+        // Insert a default row first, then update the single column. Using a
+        // fixed column name (from a closed enum) keeps the statement safe.
+        conn.execute(
+            "INSERT OR IGNORE INTO chat_config
+                (share_id, indexing_enabled, searchable, alias, excluded)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            rusqlite::params![
+                share_id,
+                default.indexing_enabled as i64,
+                default.searchable as i64,
+                default.alias,
+                default.excluded as i64,
+            ],
+        )
+        .map_err(|e| Error::Config(e.to_string()))?;
+        let sql = format!(
+            "UPDATE chat_config SET {} = ?2 WHERE share_id = ?1",
+            Self::flag_column(flag)
+        );
+        conn.execute(&sql, rusqlite::params![share_id, value as i64])
+            .map_err(|e| Error::Config(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn set_alias(&self, share_id: i64, alias: Option<String>) -> Result<()> {
+        let conn = self.conn.lock().await;
+        let default = ChatConfig::default_for(share_id);
+        conn.execute(
+            "INSERT INTO chat_config
+                (share_id, indexing_enabled, searchable, alias, excluded)
+             VALUES (?1, ?2, ?3, ?4, ?5)
+             ON CONFLICT(share_id) DO UPDATE SET alias = excluded.alias",
+            rusqlite::params![
+                share_id,
+                default.indexing_enabled as i64,
+                default.searchable as i64,
+                alias,
+                default.excluded as i64,
+            ],
+        )
+        .map_err(|e| Error::Config(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn all(&self) -> Result<Vec<ChatConfig>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn
+            .prepare(
+                "SELECT share_id, indexing_enabled, searchable, alias, excluded FROM chat_config",
+            )
+            .map_err(|e| Error::Config(e.to_string()))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok(ChatConfig {
+                    share_id: row.get::<_, i64>(0)?,
+                    indexing_enabled: row.get::<_, i64>(1)? != 0,
+                    searchable: row.get::<_, i64>(2)? != 0,
+                    alias: row.get::<_, Option<String>>(3)?,
+                    excluded: row.get::<_, i64>(4)? != 0,
+                })
+            })
+            .map_err(|e| Error::Config(e.to_string()))?;
+
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r.map_err(|e| Error::Config(e.to_string()))?);
+        }
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_in_mem_chat_config_store() {
+        let store = InMemChatConfigStore::new();
+        assert!(store.get(7).await.unwrap().is_none());
+
+        // Unseen chats upsert a default row before the flag is applied.
+        store
+            .set_flag(7, ChatFlag::IndexingEnabled, false)
+            .await
+            .unwrap();
+        let cfg = store.get(7).await.unwrap().unwrap();
+        assert!(!cfg.indexing_enabled);
+        assert!(cfg.searchable);
+        assert!(!cfg.excluded);
+
+        store.set_flag(7, ChatFlag::Excluded, true).await.unwrap();
+        store
+            .set_alias(7, Some("Rustaceans".to_string()))
+            .await
+            .unwrap();
+        let cfg = store.get(7).await.unwrap().unwrap();
+        assert!(cfg.excluded);
+        assert_eq!(cfg.alias.as_deref(), Some("Rustaceans"));
+        assert_eq!(store.all().await.unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,211 @@
+//! Persistent, pluggable store for chat metadata (names, types, last-seen)
+//!
+//! `ClientSession` historically kept the chat-name cache purely in memory and
+//! rebuilt it on every start by scanning all dialogs. This module provides a
+//! backend-agnostic interface so the snapshot can be persisted and reused
+//! across restarts (and by searchers resolving chat names offline), with an
+//! in-memory implementation preserving the old behavior and a SQLite
+//! implementation kept in its own file next to the session database.
+
+use crate::types::{Error, Result};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Metadata recorded for a single chat, keyed by its normalized `share_id`.
+#[derive(Debug, Clone)]
+pub struct ChatMeta {
+    /// Display name of the chat.
+    pub name: String,
+
+    /// Peer type as a short tag (`user` / `group` / `channel`).
+    pub chat_type: String,
+
+    /// Unix timestamp (seconds) the chat was last observed.
+    pub last_seen: i64,
+}
+
+/// Backend-agnostic store mapping `share_id -> ChatMeta`.
+#[async_trait]
+pub trait ChatMetaStore: Send + Sync {
+    /// Look up metadata for a single chat.
+    async fn get(&self, share_id: i64) -> Result<Option<ChatMeta>>;
+
+    /// Insert or replace metadata for a chat.
+    async fn insert(&self, share_id: i64, meta: ChatMeta) -> Result<()>;
+
+    /// Return a snapshot of every known chat.
+    async fn all(&self) -> Result<Vec<(i64, ChatMeta)>>;
+
+    /// Remove a chat from the store.
+    #[allow(dead_code)]
+    async fn remove(&self, share_id: i64) -> Result<()>;
+}
+
+/// In-memory store backed by a [`DashMap`] (the historical behavior).
+#[derive(Clone, Default)]
+pub struct InMemChatMetaStore {
+    data: Arc<DashMap<i64, ChatMeta>>,
+}
+
+impl InMemChatMetaStore {
+    /// Create an empty in-memory store.
+    pub fn new() -> Self {
+        Self {
+            data: Arc::new(DashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl ChatMetaStore for InMemChatMetaStore {
+    async fn get(&self, share_id: i64) -> Result<Option<ChatMeta>> {
+        Ok(self.data.get(&share_id).map(|v| v.clone()))
+    }
+
+    async fn insert(&self, share_id: i64, meta: ChatMeta) -> Result<()> {
+        self.data.insert(share_id, meta);
+        Ok(())
+    }
+
+    async fn all(&self) -> Result<Vec<(i64, ChatMeta)>> {
+        Ok(self
+            .data
+            .iter()
+            .map(|e| (*e.key(), e.value().clone()))
+            .collect())
+    }
+
+    async fn remove(&self, share_id: i64) -> Result<()> {
+        self.data.remove(&share_id);
+        Ok(())
+    }
+}
+
+/// SQLite-backed store reusing the session database directory.
+///
+/// The metadata lives in a dedicated `chat_meta` table so it can be queried
+/// independently of grammers' own session state.
+pub struct SqliteChatMetaStore {
+    conn: tokio::sync::Mutex<rusqlite::Connection>,
+}
+
+impl SqliteChatMetaStore {
+    /// Open (creating if necessary) the metadata table in `db_path`.
+    pub fn open(db_path: &Path) -> Result<Self> {
+        let conn = rusqlite::Connection::open(db_path)
+            .map_err(|e| Error::Config(format!("Failed to open chat meta db: {}", e)))?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS chat_meta (
+                share_id  INTEGER PRIMARY KEY,
+                name      TEXT NOT NULL,
+                chat_type TEXT NOT NULL,
+                last_seen INTEGER NOT NULL
+            )",
+            [],
+        )
+        .map_err(|e| Error::Config(format!("Failed to create chat_meta table: {}", e)))?;
+        Ok(Self {
+            conn: tokio::sync::Mutex::new(conn),
+        })
+    }
+}
+
+#[async_trait]
+impl ChatMetaStore for SqliteChatMetaStore {
+    async fn get(&self, share_id: i64) -> Result<Option<ChatMeta>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn
+            .prepare("SELECT name, chat_type, last_seen FROM chat_meta WHERE share_id = ?1")
+            .map_err(|e| Error::Config(e.to_string()))?;
+        let row = stmt
+            .query_row([share_id], |row| {
+                Ok(ChatMeta {
+                    name: row.get::<_, String>(0)?,
+                    chat_type: row.get::<_, String>(1)?,
+                    last_seen: row.get::<_, i64>(2)?,
+                })
+            })
+            .ok();
+        Ok(row)
+    }
+
+    async fn insert(&self, share_id: i64, meta: ChatMeta) -> Result<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            "INSERT INTO chat_meta (share_id, name, chat_type, last_seen)
+             VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(share_id) DO UPDATE SET
+                name = excluded.name,
+                chat_type = excluded.chat_type,
+                last_seen = excluded.last_seen",
+            rusqlite::params![share_id, meta.name, meta.chat_type, meta.last_seen],
+        )
+        .map_err(|e| Error::Config(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn all(&self) -> Result<Vec<(i64, ChatMeta)>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn
+            .prepare("SELECT share_id, name, chat_type, last_seen FROM chat_meta")
+            .map_err(|e| Error::Config(e.to_string()))?;
+        let rows = stmt
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    ChatMeta {
+                        name: row.get::<_, String>(1)?,
+                        chat_type: row.get::<_, String>(2)?,
+                        last_seen: row.get::<_, i64>(3)?,
+                    },
+                ))
+            })
+            .map_err(|e| Error::Config(e.to_string()))?;
+
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r.map_err(|e| Error::Config(e.to_string()))?);
+        }
+        Ok(out)
+    }
+
+    async fn remove(&self, share_id: i64) -> Result<()> {
+        let conn = self.conn.lock().await;
+        conn.execute("DELETE FROM chat_meta WHERE share_id = ?1", [share_id])
+            .map_err(|e| Error::Config(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_in_mem_chat_meta_store() {
+        let store = InMemChatMetaStore::new();
+        assert!(store.get(42).await.unwrap().is_none());
+
+        store
+            .insert(
+                42,
+                ChatMeta {
+                    name: "Rust".to_string(),
+                    chat_type: "channel".to_string(),
+                    last_seen: 100,
+                },
+            )
+            .await
+            .unwrap();
+
+        let meta = store.get(42).await.unwrap().unwrap();
+        assert_eq!(meta.name, "Rust");
+        assert_eq!(meta.chat_type, "channel");
+        assert_eq!(store.all().await.unwrap().len(), 1);
+
+        store.remove(42).await.unwrap();
+        assert!(store.get(42).await.unwrap().is_none());
+    }
+}
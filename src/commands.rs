@@ -0,0 +1,336 @@
+//! Declarative command registry and parser
+//!
+//! Historically every handler re-split the message text by hand via
+//! [`crate::utils::remove_first_word`]. This module centralizes parsing: each
+//! command declares a canonical name, aliases, an argument spec, and a
+//! description, and [`CommandRegistry::parse`] matches the leading token and
+//! splits the remainder into positional and named (`key:value`) arguments.
+//!
+//! Search queries in particular carry options such as `chat:`, `from:`, and
+//! `before:`/`after:`; those surface as named arguments while the free-text
+//! query stays in the positional list.
+
+use crate::types::{Error, Result};
+use std::collections::HashMap;
+
+/// Declarative description of a single command.
+#[derive(Debug, Clone)]
+pub struct Command {
+    /// Canonical command name without the leading slash (e.g. `search`).
+    pub name: &'static str,
+
+    /// Alternative names accepted for this command.
+    pub aliases: &'static [&'static str],
+
+    /// Human-readable description of the positional/named arguments.
+    pub args: &'static str,
+
+    /// One-line description shown in generated help.
+    pub description: &'static str,
+
+    /// Whether only the configured bot owner may run this command. Reported
+    /// by [`CommandRegistry::is_admin_only`] for the caller to enforce; the
+    /// registry itself does not check the sender, since it has no notion of
+    /// who sent a message.
+    pub admin_only: bool,
+}
+
+impl Command {
+    /// Whether `token` (without the leading slash) names this command.
+    fn matches(&self, token: &str) -> bool {
+        self.name == token || self.aliases.contains(&token)
+    }
+}
+
+/// A command after parsing: the matched command name plus its arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParsedCommand {
+    /// Canonical name of the matched command.
+    pub name: String,
+
+    /// Positional arguments in order (the free-text query words).
+    pub positional: Vec<String>,
+
+    /// Named `key:value` arguments (e.g. `chat`, `from`, `before`, `after`).
+    pub named: HashMap<String, String>,
+}
+
+impl ParsedCommand {
+    /// Join the positional arguments back into a single query string.
+    pub fn query(&self) -> String {
+        self.positional.join(" ")
+    }
+
+    /// Look up a named argument.
+    pub fn named(&self, key: &str) -> Option<&str> {
+        self.named.get(key).map(|s| s.as_str())
+    }
+}
+
+/// Registry of the commands the bot understands.
+pub struct CommandRegistry {
+    commands: Vec<Command>,
+}
+
+impl CommandRegistry {
+    /// Build the registry with the bot's known commands.
+    pub fn new() -> Self {
+        Self {
+            commands: vec![
+                Command {
+                    name: "start",
+                    aliases: &[],
+                    args: "",
+                    description: "Greet the bot",
+                    admin_only: false,
+                },
+                Command {
+                    name: "search",
+                    aliases: &["s"],
+                    args: "<query> [chat:ID] [from:NAME] [before:DATE] [after:DATE]",
+                    description: "Search indexed messages, optionally filtered",
+                    admin_only: false,
+                },
+                Command {
+                    name: "export",
+                    aliases: &[],
+                    args: "[json|csv] <query>",
+                    description: "Export search results to a downloadable file",
+                    admin_only: false,
+                },
+                Command {
+                    name: "chats",
+                    aliases: &[],
+                    args: "[keyword]",
+                    description: "List monitored chats",
+                    admin_only: false,
+                },
+                Command {
+                    name: "random",
+                    aliases: &["rand"],
+                    args: "",
+                    description: "Show a random indexed message",
+                    admin_only: false,
+                },
+                Command {
+                    name: "monitor",
+                    aliases: &[],
+                    args: "",
+                    description: "Enable indexing for the chat this is sent in",
+                    admin_only: false,
+                },
+                Command {
+                    name: "unmonitor",
+                    aliases: &[],
+                    args: "",
+                    description: "Disable indexing for the chat this is sent in",
+                    admin_only: false,
+                },
+                Command {
+                    name: "help",
+                    aliases: &["h"],
+                    args: "",
+                    description: "Show this help text",
+                    admin_only: false,
+                },
+                Command {
+                    name: "download_chat",
+                    aliases: &[],
+                    args: "[chat ...] [--min ID] [--max ID]",
+                    description: "Download and index chat history",
+                    admin_only: true,
+                },
+                Command {
+                    name: "rebuild_chat",
+                    aliases: &[],
+                    args: "[chat ...]",
+                    description: "Re-fetch a chat's history and rebuild its index from scratch",
+                    admin_only: true,
+                },
+                Command {
+                    name: "monitor_chat",
+                    aliases: &[],
+                    args: "[chat ...]",
+                    description: "Add chats to the live monitoring list",
+                    admin_only: true,
+                },
+                Command {
+                    name: "unmonitor_chat",
+                    aliases: &[],
+                    args: "[chat ...]",
+                    description: "Stop monitoring a chat (keeps its indexed messages)",
+                    admin_only: true,
+                },
+                Command {
+                    name: "clear",
+                    aliases: &[],
+                    args: "all | [chat ...]",
+                    description: "Clear the index for all or specific chats",
+                    admin_only: true,
+                },
+                Command {
+                    name: "stat",
+                    aliases: &[],
+                    args: "",
+                    description: "Show index statistics",
+                    admin_only: true,
+                },
+                Command {
+                    name: "optimize",
+                    aliases: &[],
+                    args: "",
+                    description: "Merge index segments to speed up scans and searches",
+                    admin_only: true,
+                },
+                Command {
+                    name: "refresh_chat_names",
+                    aliases: &[],
+                    args: "",
+                    description: "Refresh the chat name cache",
+                    admin_only: true,
+                },
+                Command {
+                    name: "find_chat_id",
+                    aliases: &[],
+                    args: "<keyword>",
+                    description: "Find chat IDs by name",
+                    admin_only: true,
+                },
+            ],
+        }
+    }
+
+    /// Whether the canonical command `name` (as returned in
+    /// [`ParsedCommand::name`]) is restricted to the bot owner. Unknown names
+    /// are treated as not admin-only; callers are expected to have already
+    /// gotten `name` from a successful [`Self::parse`].
+    pub fn is_admin_only(&self, name: &str) -> bool {
+        self.commands
+            .iter()
+            .any(|c| c.name == name && c.admin_only)
+    }
+
+    /// Parse `text` into a [`ParsedCommand`].
+    ///
+    /// A leading `/name` (optionally `@botname` suffixed) selects the command;
+    /// text with no leading slash is treated as an implicit `search`. Tokens of
+    /// the form `key:value` become named arguments, everything else positional.
+    pub fn parse(&self, text: &str) -> Result<ParsedCommand> {
+        let trimmed = text.trim();
+
+        let (name, rest) = if let Some(stripped) = trimmed.strip_prefix('/') {
+            let (head, rest) = match stripped.find(char::is_whitespace) {
+                Some(pos) => (&stripped[..pos], &stripped[pos + 1..]),
+                None => (stripped, ""),
+            };
+            // Drop any `@botname` suffix on the command token.
+            let head = head.split('@').next().unwrap_or(head);
+            let command = self
+                .commands
+                .iter()
+                .find(|c| c.matches(head))
+                .ok_or_else(|| Error::Config(format!("Unknown command: /{}", head)))?;
+            (command.name.to_string(), rest.to_string())
+        } else {
+            // Implicit search for plain text.
+            ("search".to_string(), trimmed.to_string())
+        };
+
+        let mut positional = Vec::new();
+        let mut named = HashMap::new();
+        for token in rest.split_whitespace() {
+            if let Some((key, value)) = token.split_once(':') {
+                if !key.is_empty() && !value.is_empty() {
+                    named.insert(key.to_string(), value.to_string());
+                    continue;
+                }
+            }
+            positional.push(token.to_string());
+        }
+
+        Ok(ParsedCommand {
+            name,
+            positional,
+            named,
+        })
+    }
+
+    /// Auto-generate the `/help` text from the registry.
+    pub fn render_help(&self) -> String {
+        let mut out = String::from("Available commands:\n\n");
+        for cmd in &self.commands {
+            out.push_str(&format!("/{}", cmd.name));
+            if !cmd.args.is_empty() {
+                out.push_str(&format!(" {}", cmd.args));
+            }
+            out.push_str(&format!("\n  {}\n", cmd.description));
+            if !cmd.aliases.is_empty() {
+                out.push_str(&format!("  aliases: {}\n", cmd.aliases.join(", ")));
+            }
+        }
+        out
+    }
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_plain_text_is_search() {
+        let registry = CommandRegistry::new();
+        let parsed = registry.parse("hello world").unwrap();
+        assert_eq!(parsed.name, "search");
+        assert_eq!(parsed.query(), "hello world");
+        assert!(parsed.named.is_empty());
+    }
+
+    #[test]
+    fn test_parse_named_arguments() {
+        let registry = CommandRegistry::new();
+        let parsed = registry
+            .parse("/search 报告 chat:123 from:Alice after:2024-01-01")
+            .unwrap();
+        assert_eq!(parsed.name, "search");
+        assert_eq!(parsed.query(), "报告");
+        assert_eq!(parsed.named("chat"), Some("123"));
+        assert_eq!(parsed.named("from"), Some("Alice"));
+        assert_eq!(parsed.named("after"), Some("2024-01-01"));
+    }
+
+    #[test]
+    fn test_parse_alias_and_botname_suffix() {
+        let registry = CommandRegistry::new();
+        let parsed = registry.parse("/s@my_bot foo").unwrap();
+        assert_eq!(parsed.name, "search");
+        assert_eq!(parsed.query(), "foo");
+    }
+
+    #[test]
+    fn test_parse_unknown_command_errors() {
+        let registry = CommandRegistry::new();
+        assert!(registry.parse("/nope arg").is_err());
+    }
+
+    #[test]
+    fn test_render_help_lists_commands() {
+        let registry = CommandRegistry::new();
+        let help = registry.render_help();
+        assert!(help.contains("/search"));
+        assert!(help.contains("/help"));
+    }
+
+    #[test]
+    fn test_is_admin_only() {
+        let registry = CommandRegistry::new();
+        assert!(registry.is_admin_only("clear"));
+        assert!(!registry.is_admin_only("search"));
+        assert!(!registry.is_admin_only("nope"));
+    }
+}
@@ -0,0 +1,359 @@
+//! Optional at-rest encryption for the Tantivy index directory.
+//!
+//! [`EncryptedDirectory`] wraps `tantivy::directory::MmapDirectory` and
+//! transparently encrypts/decrypts every file's bytes with a key derived
+//! from a user-supplied passphrase via PBKDF2. The salt and iteration
+//! count live in a small plaintext header file (`.enc_header`) alongside
+//! the index, so the same passphrase re-derives the same key on reopen.
+//!
+//! Each segment file is encrypted with its own AES-256-CTR keystream,
+//! seeded by hashing the derived key together with the file's relative
+//! path, rather than persisting a per-file nonce — nothing extra needs to
+//! be stored per segment file. CTR (not an AEAD like GCM) is the
+//! deliberate choice here: `FileHandle::read_bytes` must service arbitrary
+//! byte *ranges* (mmap'd random access, which `Indexer::get_store_reader`
+//! relies on), and a CTR keystream at byte offset `n` depends only on `n`
+//! — so decrypting a sub-range never requires touching the rest of the
+//! file. Writes are buffered in memory and encrypted as one pass from
+//! offset 0 when the file is closed (segment files are written once,
+//! start to finish), which keeps the keystream aligned with what reads
+//! will later request. A path-derived keystream is safe here only because
+//! each segment path is written exactly once for the lifetime of the
+//! directory.
+//!
+//! `atomic_read`/`atomic_write` are different: Tantivy rewrites the same
+//! path (`meta.json`, and `.managed.json`) with different content on every
+//! commit, so reusing a path-derived keystream there would XOR multiple
+//! plaintexts together under the same keystream (a two-time pad). Instead
+//! each `atomic_write` generates a fresh random 16-byte nonce, prepends it
+//! to the ciphertext, and `atomic_read` reads it back out — no range
+//! access is needed for these small whole-file reads, so there's no
+//! alignment requirement to preserve.
+//!
+//! Requires the `encrypted-index` feature (pulls in `aes`/`ctr`/`pbkdf2`/
+//! `sha2`); `Indexer::new_encrypted` is the only caller.
+
+use crate::types::{Error, Result};
+use aes::Aes256;
+use ctr::cipher::{KeyIvInit, StreamCipher, StreamCipherSeek};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::io::{self, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tantivy::HasLen;
+use tantivy::directory::error::{DeleteError, LockError, OpenReadError, OpenWriteError};
+use tantivy::directory::{
+    AntiCallToken, Directory, FileHandle, Lock, MmapDirectory, OwnedBytes, TerminatingWrite,
+    WatchCallback, WatchHandle, WritePtr,
+};
+
+type Aes256Ctr = ctr::Ctr128BE<Aes256>;
+
+const PBKDF2_ITERATIONS: u32 = 200_000;
+const SALT_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+const HEADER_FILE_NAME: &str = ".enc_header";
+const HEADER_MAGIC: &[u8; 4] = b"TSE1";
+
+/// Length of the random nonce `atomic_write` prepends to each file, so
+/// repeatedly-rewritten paths (`meta.json`) never reuse a keystream.
+const ATOMIC_NONCE_LEN: usize = 16;
+
+type Key = [u8; KEY_LEN];
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> Key {
+    let mut key = [0u8; KEY_LEN];
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, PBKDF2_ITERATIONS, &mut key);
+    key
+}
+
+/// Derive this file's CTR nonce by hashing the key together with its
+/// relative path, so every file gets a distinct keystream without storing
+/// a per-file nonce anywhere.
+fn file_iv(key: &Key, path: &Path) -> [u8; 16] {
+    let mut hasher = Sha256::new();
+    hasher.update(key);
+    hasher.update(path.to_string_lossy().as_bytes());
+    let digest = hasher.finalize();
+    let mut iv = [0u8; 16];
+    iv.copy_from_slice(&digest[..16]);
+    iv
+}
+
+/// XOR `buf` with the keystream for `iv` starting at absolute byte
+/// `offset`. The same operation encrypts and decrypts (CTR mode).
+fn apply_keystream_with_iv(key: &Key, iv: &[u8; 16], offset: u64, buf: &mut [u8]) {
+    let mut cipher = Aes256Ctr::new(key.into(), iv.into());
+    cipher.seek(offset);
+    cipher.apply_keystream(buf);
+}
+
+/// XOR `buf` with the keystream starting at absolute byte `offset` within
+/// `path`'s file, using `path`'s derived (non-random) IV. Only safe for
+/// files written exactly once — see the module docs.
+fn apply_keystream_at(key: &Key, path: &Path, offset: u64, buf: &mut [u8]) {
+    apply_keystream_with_iv(key, &file_iv(key, path), offset, buf);
+}
+
+struct EncHeader {
+    salt: [u8; SALT_LEN],
+    iterations: u32,
+}
+
+/// Read the existing header, or generate a fresh random salt and persist a
+/// new one, so the first call to `EncryptedDirectory::open` for a given
+/// `index_dir` fixes the salt/iteration count for its lifetime.
+fn read_or_create_header(index_dir: &Path) -> Result<EncHeader> {
+    let path = index_dir.join(HEADER_FILE_NAME);
+    if path.exists() {
+        let bytes = std::fs::read(&path).map_err(Error::Io)?;
+        if bytes.len() != 4 + SALT_LEN + 4 || bytes[..4] != *HEADER_MAGIC {
+            return Err(Error::Index(format!(
+                "Invalid or corrupt encrypted index header at {}",
+                path.display()
+            )));
+        }
+        let mut salt = [0u8; SALT_LEN];
+        salt.copy_from_slice(&bytes[4..4 + SALT_LEN]);
+        let iterations = u32::from_le_bytes(bytes[4 + SALT_LEN..].try_into().unwrap());
+        Ok(EncHeader { salt, iterations })
+    } else {
+        use rand::Rng;
+        let mut salt = [0u8; SALT_LEN];
+        rand::rng().fill(&mut salt);
+
+        let mut bytes = Vec::with_capacity(4 + SALT_LEN + 4);
+        bytes.extend_from_slice(HEADER_MAGIC);
+        bytes.extend_from_slice(&salt);
+        bytes.extend_from_slice(&PBKDF2_ITERATIONS.to_le_bytes());
+        std::fs::write(&path, &bytes).map_err(Error::Io)?;
+
+        Ok(EncHeader {
+            salt,
+            iterations: PBKDF2_ITERATIONS,
+        })
+    }
+}
+
+/// A `tantivy::Directory` that transparently encrypts/decrypts every
+/// file's bytes on top of an `MmapDirectory`. See the module docs for the
+/// encryption scheme.
+#[derive(Clone)]
+pub struct EncryptedDirectory {
+    inner: MmapDirectory,
+    key: Arc<Key>,
+}
+
+impl fmt::Debug for EncryptedDirectory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptedDirectory").finish_non_exhaustive()
+    }
+}
+
+impl EncryptedDirectory {
+    /// Open (or initialize) an encrypted index directory at `index_dir`,
+    /// deriving the file-encryption key from `passphrase` via PBKDF2 using
+    /// the salt/iteration count in `index_dir/.enc_header` (generated on
+    /// first use). `index_dir` itself must already exist.
+    pub fn open(index_dir: &Path, passphrase: &str) -> Result<Self> {
+        let header = read_or_create_header(index_dir)?;
+        let key = derive_key(passphrase, &header.salt);
+        let _ = header.iterations; // fixed by PBKDF2_ITERATIONS; kept for header round-tripping
+        let inner = MmapDirectory::open(index_dir).map_err(|e| Error::Index(e.to_string()))?;
+        Ok(Self {
+            inner,
+            key: Arc::new(key),
+        })
+    }
+}
+
+struct EncryptedFileHandle {
+    inner: Arc<dyn FileHandle>,
+    key: Arc<Key>,
+    path: PathBuf,
+}
+
+impl fmt::Debug for EncryptedFileHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptedFileHandle")
+            .field("path", &self.path)
+            .finish()
+    }
+}
+
+impl HasLen for EncryptedFileHandle {
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+}
+
+impl FileHandle for EncryptedFileHandle {
+    fn read_bytes(&self, byte_range: Range<usize>) -> io::Result<OwnedBytes> {
+        let ciphertext = self.inner.read_bytes(byte_range.clone())?;
+        let mut plaintext = ciphertext.as_slice().to_vec();
+        apply_keystream_at(&self.key, &self.path, byte_range.start as u64, &mut plaintext);
+        Ok(OwnedBytes::new(plaintext))
+    }
+}
+
+/// Buffers a file's contents in memory and encrypts them as a single pass
+/// from offset 0 when the file is closed, keeping the keystream aligned
+/// with what [`EncryptedFileHandle::read_bytes`] will later decrypt.
+struct EncryptingWriter {
+    inner: Box<dyn TerminatingWrite>,
+    key: Arc<Key>,
+    path: PathBuf,
+    buf: Vec<u8>,
+}
+
+impl Write for EncryptingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl TerminatingWrite for EncryptingWriter {
+    fn terminate_ref(&mut self, token: AntiCallToken) -> io::Result<()> {
+        let mut ciphertext = std::mem::take(&mut self.buf);
+        apply_keystream_at(&self.key, &self.path, 0, &mut ciphertext);
+        self.inner.write_all(&ciphertext)?;
+        self.inner.terminate_ref(token)
+    }
+}
+
+impl Directory for EncryptedDirectory {
+    fn get_file_handle(&self, path: &Path) -> std::result::Result<Arc<dyn FileHandle>, OpenReadError> {
+        let inner = self.inner.get_file_handle(path)?;
+        Ok(Arc::new(EncryptedFileHandle {
+            inner,
+            key: Arc::clone(&self.key),
+            path: path.to_path_buf(),
+        }))
+    }
+
+    fn delete(&self, path: &Path) -> std::result::Result<(), DeleteError> {
+        self.inner.delete(path)
+    }
+
+    fn exists(&self, path: &Path) -> std::result::Result<bool, OpenReadError> {
+        self.inner.exists(path)
+    }
+
+    fn open_write(&self, path: &Path) -> std::result::Result<WritePtr, OpenWriteError> {
+        let inner = self.inner.open_write(path)?.into_inner().map_err(|e| {
+            OpenWriteError::wrap_io_error(e.into_error(), path.to_path_buf())
+        })?;
+        Ok(io::BufWriter::new(Box::new(EncryptingWriter {
+            inner,
+            key: Arc::clone(&self.key),
+            path: path.to_path_buf(),
+            buf: Vec::new(),
+        })))
+    }
+
+    fn atomic_read(&self, path: &Path) -> std::result::Result<Vec<u8>, OpenReadError> {
+        let raw = self.inner.atomic_read(path)?;
+        if raw.len() < ATOMIC_NONCE_LEN {
+            return Err(OpenReadError::wrap_io_error(
+                io::Error::new(io::ErrorKind::InvalidData, "truncated encrypted file (missing nonce)"),
+                path.to_path_buf(),
+            ));
+        }
+        let (nonce, ciphertext) = raw.split_at(ATOMIC_NONCE_LEN);
+        let mut data = ciphertext.to_vec();
+        apply_keystream_with_iv(&self.key, nonce.try_into().unwrap(), 0, &mut data);
+        Ok(data)
+    }
+
+    fn atomic_write(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        use rand::Rng;
+        let mut nonce = [0u8; ATOMIC_NONCE_LEN];
+        rand::rng().fill(&mut nonce);
+
+        let mut ciphertext = data.to_vec();
+        apply_keystream_with_iv(&self.key, &nonce, 0, &mut ciphertext);
+
+        let mut out = Vec::with_capacity(ATOMIC_NONCE_LEN + ciphertext.len());
+        out.extend_from_slice(&nonce);
+        out.extend_from_slice(&ciphertext);
+        self.inner.atomic_write(path, &out)
+    }
+
+    fn sync_directory(&self) -> io::Result<()> {
+        self.inner.sync_directory()
+    }
+
+    fn watch(&self, watch_callback: WatchCallback) -> tantivy::Result<WatchHandle> {
+        self.inner.watch(watch_callback)
+    }
+
+    fn acquire_lock(&self, lock: &Lock) -> std::result::Result<tantivy::directory::DirectoryLock, LockError> {
+        self.inner.acquire_lock(lock)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_header_round_trips_across_reopen() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir1 = EncryptedDirectory::open(temp_dir.path(), "correct horse battery staple").unwrap();
+        let dir2 = EncryptedDirectory::open(temp_dir.path(), "correct horse battery staple").unwrap();
+        assert_eq!(*dir1.key, *dir2.key);
+    }
+
+    #[test]
+    fn test_wrong_passphrase_derives_different_key() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir1 = EncryptedDirectory::open(temp_dir.path(), "correct horse battery staple").unwrap();
+        let dir2 = EncryptedDirectory::open(temp_dir.path(), "wrong passphrase").unwrap();
+        assert_ne!(*dir1.key, *dir2.key);
+    }
+
+    #[test]
+    fn test_keystream_supports_unaligned_random_access() {
+        let key = derive_key("passphrase", &[0u8; SALT_LEN]);
+        let path = Path::new("segment.store");
+
+        let mut plaintext = (0..1000u32).map(|i| (i % 251) as u8).collect::<Vec<u8>>();
+        let original = plaintext.clone();
+        apply_keystream_at(&key, path, 0, &mut plaintext); // encrypt, full file
+
+        // Decrypt an arbitrary unaligned sub-range directly, without
+        // touching anything before it.
+        let range = 137..613;
+        let mut chunk = plaintext[range.clone()].to_vec();
+        apply_keystream_at(&key, path, range.start as u64, &mut chunk);
+        assert_eq!(chunk, original[range]);
+    }
+
+    #[test]
+    fn test_atomic_write_does_not_reuse_keystream_across_rewrites() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = EncryptedDirectory::open(temp_dir.path(), "passphrase").unwrap();
+        let path = Path::new("meta.json");
+
+        dir.atomic_write(path, b"{\"first\":true}").unwrap();
+        let first_ciphertext = std::fs::read(temp_dir.path().join(path)).unwrap();
+
+        dir.atomic_write(path, b"{\"first\":true}").unwrap();
+        let second_ciphertext = std::fs::read(temp_dir.path().join(path)).unwrap();
+
+        // Same plaintext, two commits: if the nonce were reused, the
+        // ciphertexts (nonce included) would be byte-identical.
+        assert_ne!(first_ciphertext, second_ciphertext);
+
+        let round_tripped = dir.atomic_read(path).unwrap();
+        assert_eq!(round_tripped, b"{\"first\":true}");
+    }
+}
@@ -8,24 +8,75 @@
 const MAX_CHAT_BUTTONS: usize = 10;
 
 use crate::backend::BackendBot;
+use crate::commands::CommandRegistry;
 use crate::config::{BotFrontendConfig, FrontendConfig};
+use crate::indexer::MatchMode;
+use crate::metrics::Metrics;
 use crate::session::ClientSession;
 use crate::storage::Storage;
-use crate::types::{Result, SearchResult};
-use crate::utils::remove_first_word;
+use crate::types::{Result, SearchHit, SearchResult};
+use crate::utils::{escape_content, remove_first_word};
 use grammers_client::client::UpdatesConfiguration;
 use grammers_client::types::update::{CallbackQuery, Update};
-use grammers_client::{Client, InputMessage, button, reply_markup};
+use grammers_client::{Client, InputMessage, InvocationError, button, reply_markup};
 use grammers_mtsender::{ConnectionParams, SenderPool};
 use grammers_session::defs::PeerId;
 use grammers_tl_types as tl;
-use std::sync::Arc;
-use std::time::Instant;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
 use tracing::{debug, error, info, warn};
 
 /// Callback data for disabled/non-interactive buttons
 const NOOP_CALLBACK: &[u8] = b"noop";
 
+/// Max times to retry a single `send_message`/`edit_message` call after a
+/// `FLOOD_WAIT` RPC error before giving up.
+const MAX_FLOOD_WAIT_RETRIES: u32 = 5;
+
+/// Minimum interval between progress-message edits during `/download_chat`,
+/// so a fast download doesn't spam edits that Telegram will rate-limit anyway.
+const PROGRESS_EDIT_DEBOUNCE: Duration = Duration::from_secs(3);
+
+/// What an inline button does when tapped: fire a callback query carrying
+/// `data`, or open `url` directly (e.g. a deep link to a message).
+#[derive(Debug, Clone)]
+enum ButtonAction {
+    Callback(String),
+    Url(String),
+}
+
+/// Page size used while streaming a query's full result set to an export
+/// file, so a whole monitored chat can be exported without buffering every
+/// hit in memory at once.
+const EXPORT_PAGE_SIZE: usize = 200;
+
+/// Output format for `/export`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    /// JSON Lines: one JSON object per hit.
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    fn from_arg(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "json" | "jsonl" => Some(Self::Json),
+            "csv" => Some(Self::Csv),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "jsonl",
+            Self::Csv => "csv",
+        }
+    }
+}
+
 /// Bot frontend for user interaction
 pub struct BotFrontend {
     /// Frontend ID
@@ -43,24 +94,41 @@ pub struct BotFrontend {
     /// Storage for pagination state
     storage: Arc<dyn Storage>,
 
-    /// Configuration
-    config: BotFrontendConfig,
+    /// Configuration. Wrapped so a config reload (see `apply_config`) can
+    /// update `page_len`/`private_mode`/`private_whitelist` live; the main
+    /// loop keeps a clone of this handle for that purpose. `bot_token`,
+    /// `admin_id` and `storage` still require a restart to change.
+    config: Arc<RwLock<BotFrontendConfig>>,
 
     /// Admin user ID
     admin_id: i64,
 
     /// Bot username (set during run)
     username: Option<String>,
+
+    /// Signaled to stop `run()`'s event loop for a graceful shutdown; shared
+    /// with the supervisor task that owns this frontend (see
+    /// `crate::supervisor`).
+    shutdown: Arc<tokio::sync::Notify>,
+
+    /// Search-latency and pagination-cache counters reported on the
+    /// `/metrics` endpoint (see `crate::metrics`).
+    metrics: Metrics,
 }
 
 impl BotFrontend {
-    /// Create a new bot frontend
+    /// Create a new bot frontend. `shutdown` is notified by the supervisor
+    /// to stop `run()`'s event loop in place of killing the task. `metrics`
+    /// is shared with every other backend/frontend and the `/metrics`
+    /// endpoint.
     pub async fn new(
         frontend_id: &str,
         config: &FrontendConfig,
         backend: Arc<BackendBot>,
         storage: Arc<dyn Storage>,
         common_config: &crate::config::CommonConfig,
+        shutdown: Arc<tokio::sync::Notify>,
+        metrics: Metrics,
     ) -> Result<Self> {
         info!("Creating bot frontend: {}", frontend_id);
 
@@ -75,7 +143,7 @@ impl BotFrontend {
                 format!("frontend_{}", frontend_id),
                 common_config.api_id,
                 &common_config.api_hash,
-                common_config.parse_proxy(),
+                common_config.parse_proxy()?,
             )
             .await?,
         );
@@ -86,18 +154,149 @@ impl BotFrontend {
             session,
             client: None,
             storage,
-            config: config.config.clone(),
+            config: Arc::new(RwLock::new(config.config.clone())),
             admin_id: config.config.admin_id,
             username: None,
+            shutdown,
+            metrics,
         })
     }
 
+    /// A clone of this frontend's live config handle, for the reload
+    /// subsystem in `main` to hold onto and mutate via `apply_config`
+    /// without needing access to the `BotFrontend` itself (which is owned
+    /// by its event-loop task).
+    pub fn config_handle(&self) -> Arc<RwLock<BotFrontendConfig>> {
+        self.config.clone()
+    }
+
+    /// Apply a reloaded [`BotFrontendConfig`] to a live config handle
+    /// returned by [`BotFrontend::config_handle`]. Only `page_len`,
+    /// `private_mode` and `private_whitelist` are updated; `bot_token`,
+    /// `admin_id` and `storage` are fixed at startup and changing them
+    /// still requires a restart (logged, not silently ignored).
+    pub fn apply_config(id: &str, handle: &RwLock<BotFrontendConfig>, new_config: &BotFrontendConfig) {
+        let mut cfg = handle.write().unwrap();
+        if cfg.bot_token != new_config.bot_token || cfg.admin_id != new_config.admin_id {
+            warn!(
+                "Frontend '{}': bot_token/admin_id changed in config but require a restart to take effect",
+                id
+            );
+        }
+        if cfg.storage != new_config.storage {
+            warn!(
+                "Frontend '{}': storage backend changed in config but requires a restart to take effect",
+                id
+            );
+        }
+        cfg.page_len = new_config.page_len;
+        cfg.private_mode = new_config.private_mode;
+        cfg.private_whitelist = new_config.private_whitelist.clone();
+        info!("Frontend '{}': applied reloaded page_len/private_mode/private_whitelist", id);
+    }
+
     /// Initialize the bot (just a placeholder, real init happens in run)
     pub async fn initialize(&mut self) -> Result<()> {
+        // Reload the persisted monitored-chat set and re-register it with the
+        // backend so `/monitor_chat` survives restarts.
+        self.reload_monitored_chats().await?;
         info!("Bot frontend initialized: {}", self.id);
         Ok(())
     }
 
+    /// Storage key holding the persisted set of monitored chat ids, keyed by
+    /// frontend id like the other `self.storage` entries.
+    fn monitored_key(&self) -> String {
+        format!("{}:monitored_chats", self.id)
+    }
+
+    /// Storage key holding the resume checkpoint (lowest message id reached)
+    /// for a chat's history download, keyed by frontend id and chat.
+    fn download_checkpoint_key(&self, chat_id: i64) -> String {
+        format!("{}:download_ckpt:{}", self.id, chat_id)
+    }
+
+    /// Load a chat's download resume checkpoint, if one was recorded.
+    async fn load_download_checkpoint(&self, chat_id: i64) -> Result<Option<i32>> {
+        Ok(self
+            .storage
+            .get(&self.download_checkpoint_key(chat_id))
+            .await?
+            .and_then(|s| s.parse::<i32>().ok()))
+    }
+
+    /// Load the persisted monitored-chat set (comma-separated share ids).
+    async fn load_monitored_set(&self) -> Result<Vec<i64>> {
+        match self.storage.get(&self.monitored_key()).await? {
+            Some(raw) => Ok(raw
+                .split(',')
+                .filter(|s| !s.is_empty())
+                .filter_map(|s| s.parse::<i64>().ok())
+                .collect()),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    /// Persist the monitored-chat set, adding or removing `ids`.
+    async fn persist_monitored_set(&self, ids: &[i64], add: bool) -> Result<()> {
+        use std::collections::BTreeSet;
+        let mut set: BTreeSet<i64> = self.load_monitored_set().await?.into_iter().collect();
+        for &id in ids {
+            if add {
+                set.insert(id);
+            } else {
+                set.remove(&id);
+            }
+        }
+        let serialized = set
+            .iter()
+            .map(|id| id.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        self.storage.set(&self.monitored_key(), &serialized).await
+    }
+
+    /// Re-register the persisted monitored-chat set with the backend on
+    /// startup. Indexing itself is driven by the backend's update stream
+    /// (see [`crate::backend::BackendBot::run`]); this only restores which
+    /// chats that stream indexes.
+    ///
+    /// Once the set has ever been written (the storage key exists) it is the
+    /// authoritative source of truth: chats the backend auto-monitored from
+    /// the index but that are absent from the set (i.e. explicitly
+    /// `/unmonitor_chat`ed) are disabled, so unmonitoring survives a restart.
+    /// If the key was never written the backend's default (monitor every
+    /// indexed chat) is left untouched.
+    async fn reload_monitored_chats(&self) -> Result<()> {
+        use std::collections::HashSet;
+        let raw = match self.storage.get(&self.monitored_key()).await? {
+            Some(raw) => raw,
+            None => return Ok(()),
+        };
+        let desired: HashSet<i64> = raw
+            .split(',')
+            .filter(|s| !s.is_empty())
+            .filter_map(|s| s.parse::<i64>().ok())
+            .collect();
+
+        // Disable any auto-monitored chat that is not in the persisted set.
+        for (id, _) in self.backend.get_monitored_chats().await? {
+            if !desired.contains(&id) {
+                self.backend
+                    .set_chat_flag(id, crate::chat_config::ChatFlag::IndexingEnabled, false)
+                    .await?;
+            }
+        }
+        // Enable everything in the persisted set.
+        for &id in &desired {
+            self.backend
+                .set_chat_flag(id, crate::chat_config::ChatFlag::IndexingEnabled, true)
+                .await?;
+        }
+        info!("Restored {} monitored chat(s) from storage", desired.len());
+        Ok(())
+    }
+
     /// Run the bot event loop
     pub async fn run(&mut self) -> Result<()> {
         // Create SenderPool and Client for this bot (all in one place)
@@ -115,8 +314,9 @@ impl BotFrontend {
             crate::types::Error::Telegram(format!("Failed to check bot authorization: {}", e))
         })? {
             info!("Bot signing in with token");
+            let bot_token = self.config.read().unwrap().bot_token.clone();
             client
-                .bot_sign_in(&self.config.bot_token, self.session.api_hash())
+                .bot_sign_in(&bot_token, self.session.api_hash())
                 .await
                 .map_err(|e| crate::types::Error::Telegram(format!("Bot sign in failed: {}", e)))?;
         }
@@ -237,26 +437,39 @@ impl BotFrontend {
         );
 
         loop {
-            match updates.next().await {
-                Ok(update) => {
+            tokio::select! {
+                update = updates.next() => {
                     match update {
-                        Update::NewMessage(message) if !message.outgoing() => {
-                            if let Err(e) = self.handle_update_message(message).await {
-                                error!("Error handling bot message: {}", e);
-                            }
-                        }
-                        Update::CallbackQuery(query) => {
-                            if let Err(e) = self.handle_update_callback(query).await {
-                                error!("Error handling bot callback: {}", e);
+                        Ok(update) => {
+                            match update {
+                                Update::NewMessage(message) if !message.outgoing() => {
+                                    if let Err(e) = self.handle_update_message(message).await {
+                                        error!("Error handling bot message: {}", e);
+                                    }
+                                }
+                                Update::CallbackQuery(query) => {
+                                    if let Err(e) = self.handle_update_callback(query).await {
+                                        error!("Error handling bot callback: {}", e);
+                                    }
+                                }
+                                _ => {
+                                    // Ignore other update types
+                                }
                             }
                         }
-                        _ => {
-                            // Ignore other update types
+                        Err(e) => {
+                            // Propagate the error so the supervisor restarts us
+                            // with backoff instead of leaving the frontend dead.
+                            self.metrics.record_event_loop_error(&self.id);
+                            return Err(crate::types::Error::Telegram(format!(
+                                "Update stream error: {}",
+                                e
+                            )));
                         }
                     }
                 }
-                Err(e) => {
-                    error!("Error getting bot update: {}", e);
+                _ = self.shutdown.notified() => {
+                    info!("Frontend '{}' received shutdown signal", self.id);
                     break;
                 }
             }
@@ -288,21 +501,24 @@ impl BotFrontend {
         };
 
         // Check private mode and whitelist (admin is always allowed)
-        if self.config.private_mode
-            && sender_id != self.admin_id
-            && !self.config.private_whitelist.contains(&sender_id)
         {
-            warn!("Unauthorized user {} tried to use bot", sender_id);
-            return Ok(());
+            let cfg = self.config.read().unwrap();
+            if cfg.private_mode && sender_id != self.admin_id && !cfg.private_whitelist.contains(&sender_id)
+            {
+                warn!("Unauthorized user {} tried to use bot", sender_id);
+                return Ok(());
+            }
         }
 
         let reply_to = message.reply_to_message_id();
 
         // Route to admin or normal handler, catch errors and send to user
         let result = if sender_id == self.admin_id {
-            self.handle_admin_message(chat_id, text, reply_to).await
+            self.handle_admin_message(chat_id, sender_id, text, reply_to)
+                .await
         } else {
-            self.handle_normal_message(chat_id, text, reply_to).await
+            self.handle_normal_message(chat_id, sender_id, text, reply_to)
+                .await
         };
 
         if let Err(e) = result {
@@ -392,6 +608,16 @@ impl BotFrontend {
                 self.handle_select_chat(chat_id, message_id, chat_id_selected)
                     .await?;
             }
+            "jump_hint" => {
+                let total_pages: usize = parts[1].parse().unwrap_or(1);
+                self.handle_jump_hint(chat_id, message_id, total_pages)
+                    .await?;
+            }
+            "export" => {
+                let format = ExportFormat::from_arg(parts[1]).unwrap_or(ExportFormat::Json);
+                self.handle_export_callback(chat_id, message_id, format)
+                    .await?;
+            }
             _ => {
                 warn!("Unknown callback data: {}", data);
             }
@@ -413,6 +639,8 @@ impl BotFrontend {
 
         let query = self.storage.get(&query_key).await?;
         let chats_str = self.storage.get(&chats_key).await?;
+        self.metrics
+            .record_pagination_cache(&self.id, query.is_some());
 
         if let Some(q) = query {
             let chats: Option<Vec<i64>> =
@@ -424,11 +652,14 @@ impl BotFrontend {
             );
 
             let start_time = Instant::now();
+            let page_len = self.config.read().unwrap().page_len;
             let result = self
                 .backend
-                .search(&q, chats.as_deref(), self.config.page_len, page_num)
+                .search(&q, chats.as_deref(), page_len, page_num, MatchMode::Fuzzy)
                 .await?;
-            let used_time = start_time.elapsed().as_secs_f64();
+            let elapsed = start_time.elapsed();
+            let used_time = elapsed.as_secs_f64();
+            self.metrics.record_search(&self.id, elapsed);
 
             let response = self.render_response_text(&result, used_time).await?;
             let buttons = self.render_buttons(&result, page_num);
@@ -445,6 +676,24 @@ impl BotFrontend {
         Ok(())
     }
 
+    /// Handle the "jump to page" flow: tapping the page indicator sends a
+    /// hint; replying to the results message with a bare page number jumps
+    /// to it (see the reply check in `handle_normal_message`).
+    async fn handle_jump_hint(
+        &self,
+        chat_id: i64,
+        message_id: i32,
+        total_pages: usize,
+    ) -> Result<()> {
+        let hint = format!(
+            "💡 Reply to the results message above with a page number (1-{}) to jump directly to it.",
+            total_pages
+        );
+        self.send_message(chat_id, &hint, None).await?;
+        info!("Sent jump-to-page hint for message {}", message_id);
+        Ok(())
+    }
+
     /// Handle chat selection
     async fn handle_select_chat(
         &self,
@@ -476,6 +725,7 @@ impl BotFrontend {
     async fn handle_normal_message(
         &self,
         chat_id: i64,
+        sender_id: i64,
         text: &str,
         reply_to: Option<i32>,
     ) -> Result<()> {
@@ -483,31 +733,106 @@ impl BotFrontend {
 
         let trimmed = text.trim();
 
-        if trimmed.is_empty() || trimmed.starts_with("/start") {
+        // A bare page number replying to a search-results message jumps to
+        // that page, completing the flow started by the page-indicator button.
+        if let Some(reply_id) = reply_to
+            && let Ok(page_num) = trimmed.parse::<usize>()
+            && page_num > 0
+        {
+            let query_key = format!("{}:query_text:{}:{}", self.id, chat_id, reply_id);
+            if self.storage.get(&query_key).await?.is_some() {
+                self.handle_search_page(chat_id, reply_id, page_num).await?;
+                return Ok(());
+            }
+        }
+
+        if trimmed.is_empty() {
             return Ok(());
-        } else if trimmed.starts_with("/random") {
-            self.handle_random(chat_id).await?;
-        } else if trimmed.starts_with("/chats") {
-            self.handle_chats(chat_id, trimmed).await?;
-        } else if trimmed.starts_with("/search") {
-            self.handle_search(chat_id, 0, trimmed, reply_to).await?;
-        } else if trimmed.starts_with("/") {
-            let cmd = trimmed.split_whitespace().next().unwrap_or("");
-            let response = format!("❌ Unknown command: {}", cmd);
-            self.send_message(chat_id, &response, None).await?;
-            warn!("Unknown command: {}", cmd);
-        } else {
-            // Plain text search
-            self.handle_search(chat_id, 0, trimmed, reply_to).await?;
+        }
+
+        let registry = CommandRegistry::new();
+        let parsed = match registry.parse(trimmed) {
+            Ok(parsed) if !registry.is_admin_only(&parsed.name) => parsed,
+            _ => {
+                // Either unrecognized or an admin-only command run by a
+                // non-admin; both get the same "unknown command" treatment
+                // rather than leaking which commands exist.
+                if trimmed.starts_with('/') {
+                    let cmd = trimmed.split_whitespace().next().unwrap_or("");
+                    let response = format!("❌ Unknown command: {}", cmd);
+                    self.send_message(chat_id, &response, None).await?;
+                    warn!("Unknown command: {}", cmd);
+                }
+                return Ok(());
+            }
+        };
+
+        match parsed.name.as_str() {
+            "start" => {}
+            "random" => self.handle_random(chat_id).await?,
+            "chats" => self.handle_chats(chat_id, trimmed).await?,
+            "search" => self.handle_search(chat_id, 0, trimmed, reply_to).await?,
+            "export" => self.handle_export(chat_id, trimmed, reply_to).await?,
+            "monitor" => self.handle_monitor_toggle(chat_id, sender_id, true).await?,
+            "unmonitor" => self.handle_monitor_toggle(chat_id, sender_id, false).await?,
+            "help" => {
+                self.send_message(chat_id, &registry.render_help(), None)
+                    .await?;
+            }
+            other => unreachable!("registered non-admin command has no handler: {}", other),
         }
 
         Ok(())
     }
 
+    /// /monitor, /unmonitor - toggle indexing for the chat the command was
+    /// sent from, gated by chat-admin authorization.
+    ///
+    /// The requester is allowed if they are the configured bot owner, if
+    /// this is a private chat (the requester implicitly owns it), or if they
+    /// are a creator/administrator of the chat; everyone else is rejected
+    /// with an explicit message rather than silently ignored.
+    async fn handle_monitor_toggle(&self, chat_id: i64, sender_id: i64, enable: bool) -> Result<()> {
+        let (_, peer_type) = crate::utils::resolve_id(chat_id);
+        let authorized = sender_id == self.admin_id
+            || peer_type == crate::utils::PeerType::User
+            || self.backend.is_chat_admin(chat_id, sender_id).await?;
+
+        if !authorized {
+            self.send_message(
+                chat_id,
+                "❌ Only this chat's admins (or the bot owner) can use this command here.",
+                None,
+            )
+            .await?;
+            return Ok(());
+        }
+
+        // Persist the set first; only confirm once it is durably stored.
+        self.persist_monitored_set(&[chat_id], enable).await?;
+        self.backend
+            .set_chat_flag(
+                chat_id,
+                crate::chat_config::ChatFlag::IndexingEnabled,
+                enable,
+            )
+            .await?;
+
+        let response = if enable {
+            "✅ Monitoring enabled for this chat."
+        } else {
+            "✅ Monitoring disabled for this chat."
+        };
+        self.send_message(chat_id, response, None).await?;
+
+        Ok(())
+    }
+
     /// Handle admin message
     async fn handle_admin_message(
         &self,
         chat_id: i64,
+        sender_id: i64,
         text: &str,
         reply_to: Option<i32>,
     ) -> Result<()> {
@@ -515,22 +840,35 @@ impl BotFrontend {
 
         let trimmed = text.trim();
 
-        if trimmed.starts_with("/stat") {
-            self.handle_stat(chat_id).await?;
-        } else if trimmed.starts_with("/download_chat") {
-            self.handle_download_chat(chat_id, trimmed, reply_to)
-                .await?;
-        } else if trimmed.starts_with("/monitor_chat") {
-            self.handle_monitor_chat(chat_id, trimmed, reply_to).await?;
-        } else if trimmed.starts_with("/clear") {
-            self.handle_clear(chat_id, trimmed, reply_to).await?;
-        } else if trimmed.starts_with("/refresh_chat_names") {
-            self.handle_refresh_chat_names(chat_id).await?;
-        } else if trimmed.starts_with("/find_chat_id") {
-            self.handle_find_chat_id(chat_id, trimmed).await?;
-        } else {
-            // Fallback to normal handler
-            self.handle_normal_message(chat_id, text, reply_to).await?;
+        let registry = CommandRegistry::new();
+        let admin_command = registry
+            .parse(trimmed)
+            .ok()
+            .filter(|parsed| registry.is_admin_only(&parsed.name));
+
+        match admin_command.as_ref().map(|parsed| parsed.name.as_str()) {
+            Some("stat") => self.handle_stat(chat_id).await?,
+            Some("optimize") => self.handle_optimize(chat_id).await?,
+            Some("rebuild_chat") => self.handle_rebuild_chat(chat_id, trimmed, reply_to).await?,
+            Some("download_chat") => {
+                self.handle_download_chat(chat_id, trimmed, reply_to)
+                    .await?
+            }
+            Some("monitor_chat") => self.handle_monitor_chat(chat_id, trimmed, reply_to).await?,
+            Some("unmonitor_chat") => {
+                self.handle_unmonitor_chat(chat_id, trimmed, reply_to)
+                    .await?
+            }
+            Some("clear") => self.handle_clear(chat_id, trimmed, reply_to).await?,
+            Some("refresh_chat_names") => self.handle_refresh_chat_names(chat_id).await?,
+            Some("find_chat_id") => self.handle_find_chat_id(chat_id, trimmed).await?,
+            Some(other) => unreachable!("registered admin command has no handler: {}", other),
+            None => {
+                // Not an admin-only command: fall back to the normal handler
+                // (still available to the admin, e.g. /search, /help).
+                self.handle_normal_message(chat_id, sender_id, text, reply_to)
+                    .await?;
+            }
         }
 
         Ok(())
@@ -608,10 +946,13 @@ impl BotFrontend {
         response.push_str("Select a chat to search within it:");
 
         // Create inline buttons - one per row
-        let buttons: Vec<Vec<(String, String)>> = display_chats
+        let buttons: Vec<Vec<(String, ButtonAction)>> = display_chats
             .iter()
             .map(|(chat_id, chat_name)| {
-                vec![(chat_name.to_string(), format!("select_chat={}", chat_id))]
+                vec![(
+                    chat_name.to_string(),
+                    ButtonAction::Callback(format!("select_chat={}", chat_id)),
+                )]
             })
             .collect();
 
@@ -629,7 +970,9 @@ impl BotFrontend {
         Ok(())
     }
 
-    /// /search or plain text - Search messages
+    /// /search or plain text - Search messages, optionally filtered by
+    /// `chat:ID`, `from:NAME`, `before:DATE` and/or `after:DATE` named
+    /// arguments (`DATE` is `YYYY-MM-DD` or a full RFC3339 timestamp).
     async fn handle_search(
         &self,
         chat_id: i64,
@@ -643,31 +986,46 @@ impl BotFrontend {
             return Ok(());
         }
 
-        // Parse query
-        let mut query = text.to_string();
-        if query.starts_with('/') || query.starts_with('@') {
-            if let Some(space_pos) = query.find(' ') {
-                query = query[space_pos + 1..].to_string();
-            } else {
-                query.clear();
-            }
-        }
+        let registry = CommandRegistry::new();
+        let parsed = registry.parse(text)?;
 
+        let mut query = parsed.query();
         if query.is_empty() {
             return Ok(());
         }
+        if let Some(sender) = parsed.named("from") {
+            query.push_str(&format!(" sender:{}", sender));
+        }
+        if let Some(clause) = build_post_time_range_clause(parsed.named("after"), parsed.named("before"))? {
+            query.push_str(&format!(" {}", clause));
+        }
 
-        // Get selected chat from reply
-        let chats = self.query_selected_chat(chat_id, reply_to).await?;
+        // A `chat:` filter is an explicit instruction in the query text, so
+        // it takes priority over the chat implicitly selected by replying to
+        // a /chats button.
+        let chats = if let Some(chat_arg) = parsed.named("chat") {
+            let filter_chat_id: i64 = chat_arg.parse().map_err(|_| {
+                crate::types::Error::Config(format!(
+                    "Invalid chat:{} filter, expected a chat ID",
+                    chat_arg
+                ))
+            })?;
+            Some(vec![filter_chat_id])
+        } else {
+            self.query_selected_chat(chat_id, reply_to).await?
+        };
 
         info!("Search \"{}\" in chats {:?}", query, chats);
 
         let start_time = Instant::now();
+        let page_len = self.config.read().unwrap().page_len;
         let result = self
             .backend
-            .search(&query, chats.as_deref(), self.config.page_len, 1)
+            .search(&query, chats.as_deref(), page_len, 1, MatchMode::Fuzzy)
             .await?;
-        let used_time = start_time.elapsed().as_secs_f64();
+        let elapsed = start_time.elapsed();
+        let used_time = elapsed.as_secs_f64();
+        self.metrics.record_search(&self.id, elapsed);
 
         let response = self.render_response_text(&result, used_time).await?;
         let buttons = self.render_buttons(&result, 1);
@@ -693,6 +1051,211 @@ impl BotFrontend {
         Ok(())
     }
 
+    /// /export [json|csv] <query> - Export search results to a downloadable file
+    async fn handle_export(&self, chat_id: i64, text: &str, reply_to: Option<i32>) -> Result<()> {
+        if self.backend.is_empty(None).await? {
+            let response = "Index is empty. Please use /download_chat to build the index first";
+            self.send_message(chat_id, response, None).await?;
+            return Ok(());
+        }
+
+        let args = shell_words::split(text)
+            .map_err(|e| crate::types::Error::Config(format!("Failed to parse command: {}", e)))?;
+
+        let mut rest = args.into_iter().skip(1).peekable();
+        let format = match rest.peek().and_then(|a| ExportFormat::from_arg(a)) {
+            Some(f) => {
+                rest.next();
+                f
+            }
+            None => ExportFormat::Json,
+        };
+        let query: String = rest.collect::<Vec<_>>().join(" ");
+
+        if query.is_empty() {
+            self.send_message(chat_id, "❌ Usage: /export [json|csv] <query>", None)
+                .await?;
+            return Ok(());
+        }
+
+        let chats = self.query_selected_chat(chat_id, reply_to).await?;
+        self.export_and_send(chat_id, &query, chats.as_deref(), format)
+            .await
+    }
+
+    /// Export triggered by the "Export" button on a result page: re-run the
+    /// query stashed for that message (the same lookup pagination uses).
+    async fn handle_export_callback(
+        &self,
+        chat_id: i64,
+        message_id: i32,
+        format: ExportFormat,
+    ) -> Result<()> {
+        let query_key = format!("{}:query_text:{}:{}", self.id, chat_id, message_id);
+        let chats_key = format!("{}:query_chats:{}:{}", self.id, chat_id, message_id);
+
+        let query = self.storage.get(&query_key).await?;
+        let chats_str = self.storage.get(&chats_key).await?;
+
+        if let Some(q) = query {
+            let chats: Option<Vec<i64>> =
+                chats_str.map(|s| s.split(',').filter_map(|id| id.parse().ok()).collect());
+            self.export_and_send(chat_id, &q, chats.as_deref(), format)
+                .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Stream a query's full result set into a temp file (one page of
+    /// `EXPORT_PAGE_SIZE` hits at a time, so a whole monitored chat can be
+    /// exported without buffering every hit in memory) and upload it back to
+    /// the chat as a document.
+    async fn export_and_send(
+        &self,
+        chat_id: i64,
+        query: &str,
+        chats: Option<&[i64]>,
+        format: ExportFormat,
+    ) -> Result<()> {
+        let unique = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        let tmp_path = std::env::temp_dir().join(format!(
+            "tg_searcher_export_{}_{}_{}.{}",
+            self.id,
+            chat_id,
+            unique,
+            format.extension()
+        ));
+
+        let mut file = tokio::fs::File::create(&tmp_path).await?;
+        if format == ExportFormat::Csv {
+            file.write_all(b"chat,sender,post_time,url,content\n")
+                .await?;
+        }
+
+        // Pre-translate chat names as pages come in, same as render_response_text,
+        // but cached across pages so each chat is only looked up once.
+        let mut chat_names: HashMap<i64, String> = HashMap::new();
+        let mut exported = 0usize;
+        let mut page_num = 1;
+        loop {
+            let result = self
+                .backend
+                .search(query, chats, EXPORT_PAGE_SIZE, page_num, MatchMode::Exact)
+                .await?;
+            if result.hits.is_empty() {
+                break;
+            }
+
+            let unique_chat_ids: HashSet<_> =
+                result.hits.iter().map(|hit| hit.msg.chat_id).collect();
+            for chat_id in unique_chat_ids {
+                if let std::collections::hash_map::Entry::Vacant(e) = chat_names.entry(chat_id) {
+                    e.insert(self.backend.translate_chat_id(chat_id).await?);
+                }
+            }
+
+            for hit in &result.hits {
+                let chat_title = &chat_names[&hit.msg.chat_id];
+                let line = match format {
+                    ExportFormat::Json => Self::hit_to_json_line(hit, chat_title),
+                    ExportFormat::Csv => Self::hit_to_csv_line(hit, chat_title),
+                };
+                file.write_all(line.as_bytes()).await?;
+            }
+            exported += result.hits.len();
+
+            if result.is_last_page {
+                break;
+            }
+            page_num += 1;
+        }
+        file.flush().await?;
+        drop(file);
+
+        if exported == 0 {
+            let _ = tokio::fs::remove_file(&tmp_path).await;
+            self.send_message(chat_id, "❌ No results to export", None)
+                .await?;
+            return Ok(());
+        }
+
+        let client = self.client.as_ref().ok_or_else(|| {
+            crate::types::Error::Config("Frontend client not initialized".to_string())
+        })?;
+        let uploaded = client.upload_file(&tmp_path).await.map_err(|e| {
+            crate::types::Error::Telegram(format!("Failed to upload export file: {}", e))
+        })?;
+
+        let peer = Self::chat_id_to_input_peer_static(chat_id);
+        let caption = format!("📄 Exported {} result(s) ({:?})", exported, format);
+        let message = InputMessage::new().html(&caption).file(uploaded);
+        client.send_message(peer, message).await.map_err(|e| {
+            crate::types::Error::Telegram(format!("Failed to send export file: {}", e))
+        })?;
+
+        let _ = tokio::fs::remove_file(&tmp_path).await;
+        info!(
+            "Exported {} results ({:?}) for chat {}",
+            exported, format, chat_id
+        );
+        Ok(())
+    }
+
+    /// Serialize one hit as a JSON Lines object, using the hit's raw
+    /// (un-highlighted) content rather than the search-highlighted HTML.
+    fn hit_to_json_line(hit: &SearchHit, chat_title: &str) -> String {
+        format!(
+            "{{\"chat\":\"{}\",\"sender\":\"{}\",\"post_time\":\"{}\",\"url\":\"{}\",\"content\":\"{}\"}}\n",
+            Self::json_escape(chat_title),
+            Self::json_escape(&hit.msg.sender),
+            hit.msg.post_time.to_rfc3339(),
+            Self::json_escape(&hit.msg.url),
+            Self::json_escape(&hit.msg.content),
+        )
+    }
+
+    /// Serialize one hit as a CSV row (RFC 4180 quoting).
+    fn hit_to_csv_line(hit: &SearchHit, chat_title: &str) -> String {
+        format!(
+            "{},{},{},{},{}\n",
+            Self::csv_escape(chat_title),
+            Self::csv_escape(&hit.msg.sender),
+            hit.msg.post_time.to_rfc3339(),
+            Self::csv_escape(&hit.msg.url),
+            Self::csv_escape(&hit.msg.content),
+        )
+    }
+
+    /// Minimal JSON string escaping (no external JSON dependency).
+    fn json_escape(value: &str) -> String {
+        let mut out = String::with_capacity(value.len() + 2);
+        for ch in value.chars() {
+            match ch {
+                '"' => out.push_str("\\\""),
+                '\\' => out.push_str("\\\\"),
+                '\n' => out.push_str("\\n"),
+                '\r' => out.push_str("\\r"),
+                '\t' => out.push_str("\\t"),
+                c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+                c => out.push(c),
+            }
+        }
+        out
+    }
+
+    /// Quote a CSV field if it contains a comma, quote, or newline.
+    fn csv_escape(value: &str) -> String {
+        if value.contains(',') || value.contains('"') || value.contains('\n') {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        } else {
+            value.to_string()
+        }
+    }
+
     /// /stat - Get index status
     async fn handle_stat(&self, chat_id: i64) -> Result<()> {
         let status = self
@@ -704,6 +1267,16 @@ impl BotFrontend {
         Ok(())
     }
 
+    /// /optimize - Merge index segments to speed up scans and searches
+    async fn handle_optimize(&self, chat_id: i64) -> Result<()> {
+        self.send_message(chat_id, "Optimizing index, this may take a while...", None)
+            .await?;
+        self.backend.optimize_index().await?;
+        self.send_message(chat_id, "Index optimized.", None).await?;
+        info!("Optimized index");
+        Ok(())
+    }
+
     /// /download_chat - Download and index chat history
     async fn handle_download_chat(
         &self,
@@ -717,6 +1290,8 @@ impl BotFrontend {
 
         let mut min_id: Option<i32> = None;
         let mut max_id: Option<i32> = None;
+        let mut resume = false;
+        let mut incremental = false;
         let mut chat_args = Vec::new();
 
         let mut i = 1; // Skip command itself
@@ -727,6 +1302,12 @@ impl BotFrontend {
             } else if args[i] == "--max" && i + 1 < args.len() {
                 max_id = args[i + 1].parse().ok();
                 i += 2;
+            } else if args[i] == "--resume" {
+                resume = true;
+                i += 1;
+            } else if args[i] == "--incremental" {
+                incremental = true;
+                i += 1;
             } else {
                 chat_args.push(args[i].clone());
                 i += 1;
@@ -757,45 +1338,84 @@ impl BotFrontend {
 
         for &target_chat_id in &ids {
             info!(
-                "Start downloading history of {} (min={:?}, max={:?})",
-                target_chat_id, min_id, max_id
+                "Start downloading history of {} (min={:?}, max={:?}, resume={}, incremental={})",
+                target_chat_id, min_id, max_id, resume, incremental
             );
 
-            // Check if chat already has indexed documents
+            // Resolve the effective range and a human-readable mode label.
+            // --resume continues backward from the stored checkpoint;
+            // --incremental only fetches messages newer than the highest
+            // already-indexed id; otherwise the historical fresh-download
+            // behavior (with the already-indexed guard) applies.
             let is_empty = self.backend.is_empty(Some(target_chat_id)).await?;
-            if !is_empty && min_id.is_none() && max_id.is_none() {
-                let warning = format!(
-                    "⚠️ Chat {} already has indexed messages.\n\n\
-                    To download history:\n\
-                    1. Use /clear {} first to remove existing index, OR\n\
-                    2. Specify min_id or max_id to download specific range\n\n\
-                    Example: /download_chat {} --min 12345",
-                    target_chat_id, target_chat_id, target_chat_id
-                );
-                self.send_message(chat_id, &warning, None).await?;
-                continue;
-            }
+            let (eff_min, eff_max, mode) = if resume {
+                match self.load_download_checkpoint(target_chat_id).await? {
+                    Some(ckpt) => (min_id, Some(ckpt), "resume"),
+                    None => (min_id, max_id, "fresh"),
+                }
+            } else if incremental {
+                match self.backend.max_indexed_msg_id(target_chat_id).await? {
+                    Some(high) => (Some(high + 1), None, "incremental"),
+                    None => (min_id, max_id, "fresh"),
+                }
+            } else {
+                if !is_empty && min_id.is_none() && max_id.is_none() {
+                    let warning = format!(
+                        "⚠️ Chat {} already has indexed messages.\n\n\
+                        To download history:\n\
+                        1. Use /clear {} first to remove existing index, OR\n\
+                        2. Specify min_id or max_id to download specific range, OR\n\
+                        3. Use --resume to continue a previous download, or \
+                        --incremental to fetch only newer messages\n\n\
+                        Example: /download_chat {} --min 12345",
+                        target_chat_id, target_chat_id, target_chat_id
+                    );
+                    self.send_message(chat_id, &warning, None).await?;
+                    continue;
+                }
+                (min_id, max_id, "fresh")
+            };
 
-            // Send initial progress message
-            let progress_msg_id = self
-                .send_message(
-                    chat_id,
-                    &format!("📥 Starting history fetch from chat {}...", target_chat_id),
-                    None,
-                )
-                .await?;
+            // Send initial progress message, noting the resume point when
+            // continuing a prior interrupted/incremental download.
+            let start_text = match mode {
+                "resume" | "incremental" => format!(
+                    "📥 Resuming history fetch from chat {} (msg_id {})...",
+                    target_chat_id,
+                    eff_max.or(eff_min).unwrap_or(0)
+                ),
+                _ => format!("📥 Starting history fetch from chat {}...", target_chat_id),
+            };
+            let progress_msg_id = self.send_message(chat_id, &start_text, None).await?;
 
             // Create channel for progress updates
             let (progress_tx, mut progress_rx) =
                 tokio::sync::mpsc::unbounded_channel::<crate::types::DownloadProgress>();
 
-            // Spawn task to edit progress message
+            // Spawn task to edit progress message and checkpoint the lowest
+            // message id reached so an interrupted download can be resumed.
             let frontend_chat_id = chat_id;
             let send_client = self.client.clone().ok_or_else(|| {
                 crate::types::Error::Config("Frontend client not initialized".to_string())
             })?;
+            let ckpt_storage = Arc::clone(&self.storage);
+            let ckpt_key = self.download_checkpoint_key(target_chat_id);
             let callback_task = tokio::spawn(async move {
+                // Debounce: only edit the progress message at most once every
+                // `PROGRESS_EDIT_DEBOUNCE`, but always flush the latest state
+                // once the channel closes so the final count isn't dropped.
+                let mut last_edit: Option<Instant> = None;
+                let mut pending: Option<crate::types::DownloadProgress> = None;
                 while let Some(progress) = progress_rx.recv().await {
+                    // Record the resume checkpoint (lowest id reached so far).
+                    let _ = ckpt_storage
+                        .set(&ckpt_key, &progress.latest_msg_id.to_string())
+                        .await;
+                    pending = Some(progress);
+                    if last_edit.is_some_and(|t| t.elapsed() < PROGRESS_EDIT_DEBOUNCE) {
+                        continue;
+                    }
+                    let progress = pending.take().expect("just set");
                     let msg = format!(
                         "📥 Fetching history from chat {}...\n{} messages fetched (latest: msg_id {})",
                         progress.chat_id, progress.downloaded, progress.latest_msg_id
@@ -809,6 +1429,21 @@ impl BotFrontend {
                         None,
                     )
                     .await;
+                    last_edit = Some(Instant::now());
+                }
+                if let Some(progress) = pending {
+                    let msg = format!(
+                        "📥 Fetching history from chat {}...\n{} messages fetched (latest: msg_id {})",
+                        progress.chat_id, progress.downloaded, progress.latest_msg_id
+                    );
+                    let _ = Self::edit_message_with_client(
+                        &send_client,
+                        frontend_chat_id,
+                        progress_msg_id,
+                        &msg,
+                        None,
+                    )
+                    .await;
                 }
             });
 
@@ -818,21 +1453,40 @@ impl BotFrontend {
                 let _ = progress_tx.send(progress);
             };
 
-            let count = self
+            let outcome = self
                 .backend
-                .download_history(target_chat_id, min_id, max_id, Some(progress_callback))
+                .download_history(target_chat_id, eff_min, eff_max, Some(progress_callback))
                 .await?;
 
             callback_task.await?;
 
-            // Edit final message with completion status
+            // Persist the final checkpoint (lowest id reached this run).
+            if outcome.lowest_msg_id > 0 {
+                self.storage
+                    .set(
+                        &self.download_checkpoint_key(target_chat_id),
+                        &outcome.lowest_msg_id.to_string(),
+                    )
+                    .await?;
+            }
+
+            // Edit final message with a mode-aware completion status.
+            let mode_label = match mode {
+                "resume" => "Resumed download",
+                "incremental" => "Incremental top-up",
+                _ => "Fresh download",
+            };
+            let skipped = outcome.fetched.saturating_sub(outcome.indexed);
             let response = format!(
-                "✅ Downloaded {} messages from chat {}",
-                count, target_chat_id
+                "✅ {} of chat {}: {} new message(s) added, {} skipped",
+                mode_label, target_chat_id, outcome.indexed, skipped
             );
             self.edit_message(chat_id, progress_msg_id, &response, None)
                 .await?;
-            info!("Downloaded {} messages from {}", count, target_chat_id);
+            info!(
+                "{} of {}: {} added, {} skipped",
+                mode_label, target_chat_id, outcome.indexed, skipped
+            );
         }
 
         Ok(())
@@ -866,12 +1520,71 @@ impl BotFrontend {
         }
 
         if !ids.is_empty() {
+            // Persist the set first; only confirm once it is durably stored.
+            self.persist_monitored_set(&ids, true).await?;
+
             for &target_chat_id in &ids {
                 info!("Add {} to monitored_chats", target_chat_id);
+                self.backend
+                    .set_chat_flag(
+                        target_chat_id,
+                        crate::chat_config::ChatFlag::IndexingEnabled,
+                        true,
+                    )
+                    .await?;
                 let chat_html = self.backend.format_dialog_html(target_chat_id).await?;
                 let response = format!("{} has been added to monitoring list", chat_html);
                 self.send_message(chat_id, &response, None).await?;
-                // TODO: Actually add to backend monitored_chats
+            }
+        }
+
+        Ok(())
+    }
+
+    /// /unmonitor_chat - Stop monitoring a chat (keeps its indexed messages)
+    async fn handle_unmonitor_chat(
+        &self,
+        chat_id: i64,
+        text: &str,
+        reply_to: Option<i32>,
+    ) -> Result<()> {
+        let args = shell_words::split(text)
+            .map_err(|e| crate::types::Error::Config(format!("Failed to parse command: {}", e)))?;
+
+        let chat_args: Vec<String> = args.into_iter().skip(1).collect();
+
+        let (ids, failed) = if chat_args.is_empty() {
+            match self.query_selected_chat(chat_id, reply_to).await? {
+                Some(selected_ids) => (selected_ids, Vec::new()),
+                None => (Vec::new(), Vec::new()),
+            }
+        } else {
+            self.chat_ids_from_args(&chat_args).await
+        };
+
+        // Report failed chats
+        if !failed.is_empty() {
+            let response = format!("❌ Could not resolve: {}", failed.join(", "));
+            self.send_message(chat_id, &response, None).await?;
+        }
+
+        if !ids.is_empty() {
+            // Drop the chats from the persisted set first, then stop indexing
+            // them. The existing index is left intact (distinct from /clear).
+            self.persist_monitored_set(&ids, false).await?;
+
+            for &target_chat_id in &ids {
+                info!("Remove {} from monitored_chats", target_chat_id);
+                self.backend
+                    .set_chat_flag(
+                        target_chat_id,
+                        crate::chat_config::ChatFlag::IndexingEnabled,
+                        false,
+                    )
+                    .await?;
+                let chat_html = self.backend.format_dialog_html(target_chat_id).await?;
+                let response = format!("{} has been removed from monitoring list", chat_html);
+                self.send_message(chat_id, &response, None).await?;
             }
         }
 
@@ -965,6 +1678,64 @@ impl BotFrontend {
         Ok(())
     }
 
+    /// /rebuild_chat - Re-fetch a chat's full history and atomically
+    /// replace its indexed documents, for repairing a chat left in a bad
+    /// state by an earlier partial or corrupted download.
+    async fn handle_rebuild_chat(
+        &self,
+        chat_id: i64,
+        text: &str,
+        reply_to: Option<i32>,
+    ) -> Result<()> {
+        let args = shell_words::split(text)
+            .map_err(|e| crate::types::Error::Config(format!("Failed to parse command: {}", e)))?;
+        let chat_args: Vec<String> = args.into_iter().skip(1).collect();
+
+        let (ids, failed) = if chat_args.is_empty() {
+            match self.query_selected_chat(chat_id, reply_to).await? {
+                Some(selected_ids) => (selected_ids, Vec::new()),
+                None => {
+                    self.send_message(
+                        chat_id,
+                        "Use /rebuild_chat [CHAT ...] to specify chat names or IDs to rebuild",
+                        None,
+                    )
+                    .await?;
+                    return Ok(());
+                }
+            }
+        } else {
+            self.chat_ids_from_args(&chat_args).await
+        };
+
+        if !failed.is_empty() {
+            let response = format!("❌ Could not resolve: {}", failed.join(", "));
+            self.send_message(chat_id, &response, None).await?;
+        }
+
+        for &target_chat_id in &ids {
+            let chat_html = self.backend.format_dialog_html(target_chat_id).await?;
+            self.send_message(chat_id, &format!("Rebuilding {}...", chat_html), None)
+                .await?;
+
+            match self.backend.rebuild_chat(target_chat_id).await {
+                Ok(outcome) => {
+                    let response = format!(
+                        "✅ Rebuilt {}: fetched {}, indexed {}",
+                        chat_html, outcome.fetched, outcome.indexed
+                    );
+                    self.send_message(chat_id, &response, None).await?;
+                }
+                Err(e) => {
+                    let response = format!("❌ Failed to rebuild {}: {}", chat_html, e);
+                    self.send_message(chat_id, &response, None).await?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
     /// /refresh_chat_names - Refresh chat name cache
     async fn handle_refresh_chat_names(&self, chat_id: i64) -> Result<()> {
         // Start refresh in background (non-blocking)
@@ -1069,6 +1840,9 @@ impl BotFrontend {
             "Found {} results in {:.3} seconds:\n\n",
             result.total_results, used_time
         )];
+        if let Some(suggestion) = &result.suggestion {
+            parts.push(format!("Did you mean: <b>{}</b>?\n\n", escape_content(suggestion)));
+        }
 
         // Pre-translate unique chat IDs to avoid redundant lookups
         // Collect unique chat IDs first
@@ -1093,11 +1867,18 @@ impl BotFrontend {
                 parts.push(format!("<b>{} [{}]</b>\n", chat_title, hit.msg.post_time));
             }
 
-            // The highlighted text is already HTML with <b> tags around matches
-            parts.push(format!(
-                "<a href=\"{}\">{}</a>\n\n",
-                hit.msg.url, hit.highlighted
-            ));
+            // The highlighted text is already HTML with <b> tags around matches.
+            // `hit.msg.url` is a placeholder `tg-searcher://` URI rather than a
+            // real link for chats `build_message_key` has no deep link for
+            // (private chats, basic groups) — don't wrap the text in a dead link.
+            if hit.msg.url.starts_with("https://") {
+                parts.push(format!(
+                    "<a href=\"{}\">{}</a>\n\n",
+                    hit.msg.url, hit.highlighted
+                ));
+            } else {
+                parts.push(format!("{}\n\n", hit.highlighted));
+            }
         }
 
         Ok(parts.join(""))
@@ -1129,19 +1910,19 @@ impl BotFrontend {
 
     /// Create inline button markup from button rows (static helper)
     fn create_inline_buttons_static(
-        button_rows: Vec<Vec<(String, String)>>,
+        button_rows: Vec<Vec<(String, ButtonAction)>>,
     ) -> reply_markup::Inline {
         let rows: Vec<Vec<button::Inline>> = button_rows
             .into_iter()
             .map(|row| {
                 row.into_iter()
-                    .map(|(label, data)| {
-                        if !data.is_empty() {
+                    .map(|(label, action)| match action {
+                        ButtonAction::Url(url) => button::url(label, url),
+                        ButtonAction::Callback(data) if !data.is_empty() => {
                             button::inline(label, data.as_bytes())
-                        } else {
-                            // Empty data means disabled button (just label)
-                            button::inline(label, NOOP_CALLBACK)
                         }
+                        // Empty callback data means disabled button (just label)
+                        ButtonAction::Callback(_) => button::inline(label, NOOP_CALLBACK),
                     })
                     .collect()
             })
@@ -1149,40 +1930,85 @@ impl BotFrontend {
         reply_markup::inline(rows)
     }
 
-    /// Render pagination buttons
+    /// Render search-hit "Open" buttons plus pagination controls (First/Prev/page-jump/Next/Last)
     fn render_buttons(
         &self,
         result: &SearchResult,
         cur_page_num: usize,
-    ) -> Vec<Vec<(String, String)>> {
-        let total_pages = result.total_results.div_ceil(self.config.page_len);
+    ) -> Vec<Vec<(String, ButtonAction)>> {
+        let page_len = self.config.read().unwrap().page_len;
+        let total_pages = result.total_results.div_ceil(page_len).max(1);
+
+        let disabled = || (" ".to_string(), ButtonAction::Callback(String::new()));
+
+        // One deep-link button per hit so results are tappable without
+        // scanning the text. Private chats and basic groups have no valid
+        // `t.me` deep link (see `build_message_key`), so hits from those
+        // chats get no button rather than a dead link.
+        let mut rows: Vec<Vec<(String, ButtonAction)>> = result
+            .hits
+            .iter()
+            .filter(|hit| hit.msg.url.starts_with("https://"))
+            .map(|hit| vec![("🔗 Open".to_string(), ButtonAction::Url(hit.msg.url.clone()))])
+            .collect();
 
-        let former = if cur_page_num == 1 {
-            (" ".to_string(), "".to_string())
+        let first = if cur_page_num <= 1 {
+            disabled()
         } else {
             (
-                "Previous".to_string(),
-                format!("search_page={}", cur_page_num - 1),
+                "« First".to_string(),
+                ButtonAction::Callback("search_page=1".to_string()),
             )
         };
-
+        let prev = if cur_page_num <= 1 {
+            disabled()
+        } else {
+            (
+                "‹ Prev".to_string(),
+                ButtonAction::Callback(format!("search_page={}", cur_page_num - 1)),
+            )
+        };
+        // Tapping the page indicator starts the "jump to page" flow: it sends
+        // a hint telling the user to reply with a page number.
+        let indicator = (
+            format!("{} / {}", cur_page_num, total_pages),
+            ButtonAction::Callback(format!("jump_hint={}", total_pages)),
+        );
         let next = if result.is_last_page {
-            (" ".to_string(), "".to_string())
+            disabled()
+        } else {
+            (
+                "Next ›".to_string(),
+                ButtonAction::Callback(format!("search_page={}", cur_page_num + 1)),
+            )
+        };
+        let last = if result.is_last_page {
+            disabled()
         } else {
             (
-                "Next".to_string(),
-                format!("search_page={}", cur_page_num + 1),
+                "Last »".to_string(),
+                ButtonAction::Callback(format!("search_page={}", total_pages)),
             )
         };
 
-        vec![vec![
-            former,
+        rows.push(vec![first, prev, indicator, next, last]);
+        rows.push(vec![
+            (
+                "📄 Export JSON".to_string(),
+                ButtonAction::Callback("export=json".to_string()),
+            ),
             (
-                format!("{} / {}", cur_page_num, total_pages),
-                "".to_string(),
+                "📄 Export CSV".to_string(),
+                ButtonAction::Callback("export=csv".to_string()),
             ),
-            next,
-        ]]
+        ]);
+        rows
+    }
+
+    /// Whether `err` is Telegram's harmless "the message content/markup
+    /// didn't actually change" error, which edit_message can safely ignore.
+    fn is_message_not_modified(err: &InvocationError) -> bool {
+        matches!(err, InvocationError::Rpc(rpc) if rpc.name == "MESSAGE_NOT_MODIFIED")
     }
 
     /// Send a message to a chat (static helper)
@@ -1190,27 +2016,38 @@ impl BotFrontend {
         client: &Client,
         chat_id: i64,
         text: &str,
-        buttons: Option<Vec<Vec<(String, String)>>>,
+        buttons: Option<Vec<Vec<(String, ButtonAction)>>>,
     ) -> Result<i32> {
-        // Create InputPeer using helper
-        let peer = Self::chat_id_to_input_peer_static(chat_id);
-
-        // Create message with HTML formatting
-        let mut message = InputMessage::new().html(text);
+        let mut retries = 0;
+        loop {
+            // Create InputPeer and message fresh each attempt (InputMessage is consumed by send_message)
+            let peer = Self::chat_id_to_input_peer_static(chat_id);
+            let mut message = InputMessage::new().html(text);
+            if let Some(ref button_rows) = buttons {
+                let markup = Self::create_inline_buttons_static(button_rows.clone());
+                message = message.reply_markup(&markup);
+            }
 
-        // Add inline buttons if provided
-        if let Some(button_rows) = buttons {
-            let markup = Self::create_inline_buttons_static(button_rows);
-            message = message.reply_markup(&markup);
+            match client.send_message(peer, message).await {
+                Ok(sent) => return Ok(sent.id()),
+                Err(e) => match crate::utils::flood_wait_secs(&e) {
+                    Some(secs) if retries < MAX_FLOOD_WAIT_RETRIES => {
+                        retries += 1;
+                        warn!(
+                            "FLOOD_WAIT({}) sending message, retrying in {}s ({}/{})",
+                            secs, secs, retries, MAX_FLOOD_WAIT_RETRIES
+                        );
+                        tokio::time::sleep(Duration::from_secs(secs)).await;
+                    }
+                    _ => {
+                        return Err(crate::types::Error::Telegram(format!(
+                            "Failed to send message: {}",
+                            e
+                        )));
+                    }
+                },
+            }
         }
-
-        // Send message
-        let sent = client
-            .send_message(peer, message)
-            .await
-            .map_err(|e| crate::types::Error::Telegram(format!("Failed to send message: {}", e)))?;
-
-        Ok(sent.id())
     }
 
     /// Send a message to a chat
@@ -1218,7 +2055,7 @@ impl BotFrontend {
         &self,
         chat_id: i64,
         text: &str,
-        buttons: Option<Vec<Vec<(String, String)>>>,
+        buttons: Option<Vec<Vec<(String, ButtonAction)>>>,
     ) -> Result<i32> {
         let client = self
             .client
@@ -1233,27 +2070,39 @@ impl BotFrontend {
         chat_id: i64,
         message_id: i32,
         text: &str,
-        buttons: Option<Vec<Vec<(String, String)>>>,
+        buttons: Option<Vec<Vec<(String, ButtonAction)>>>,
     ) -> Result<()> {
-        // Create InputPeer using helper
-        let chat = Self::chat_id_to_input_peer_static(chat_id);
-
-        // Create input message with HTML formatting
-        let mut input = InputMessage::new().html(text);
+        let mut retries = 0;
+        loop {
+            // Create InputPeer and message fresh each attempt (InputMessage is consumed by edit_message)
+            let chat = Self::chat_id_to_input_peer_static(chat_id);
+            let mut input = InputMessage::new().html(text);
+            if let Some(ref button_rows) = buttons {
+                let markup = Self::create_inline_buttons_static(button_rows.clone());
+                input = input.reply_markup(&markup);
+            }
 
-        // Add inline buttons if provided
-        if let Some(button_rows) = buttons {
-            let markup = Self::create_inline_buttons_static(button_rows);
-            input = input.reply_markup(&markup);
+            match client.edit_message(chat, message_id, input).await {
+                Ok(()) => return Ok(()),
+                Err(e) if Self::is_message_not_modified(&e) => return Ok(()),
+                Err(e) => match crate::utils::flood_wait_secs(&e) {
+                    Some(secs) if retries < MAX_FLOOD_WAIT_RETRIES => {
+                        retries += 1;
+                        warn!(
+                            "FLOOD_WAIT({}) editing message, retrying in {}s ({}/{})",
+                            secs, secs, retries, MAX_FLOOD_WAIT_RETRIES
+                        );
+                        tokio::time::sleep(Duration::from_secs(secs)).await;
+                    }
+                    _ => {
+                        return Err(crate::types::Error::Telegram(format!(
+                            "Failed to edit message: {}",
+                            e
+                        )));
+                    }
+                },
+            }
         }
-
-        // Edit message
-        client
-            .edit_message(chat, message_id, input)
-            .await
-            .map_err(|e| crate::types::Error::Telegram(format!("Failed to edit message: {}", e)))?;
-
-        Ok(())
     }
 
     /// Edit a message
@@ -1262,7 +2111,7 @@ impl BotFrontend {
         chat_id: i64,
         message_id: i32,
         text: &str,
-        buttons: Option<Vec<Vec<(String, String)>>>,
+        buttons: Option<Vec<Vec<(String, ButtonAction)>>>,
     ) -> Result<()> {
         let client = self
             .client
@@ -1284,3 +2133,47 @@ impl BotFrontend {
         }
     }
 }
+
+/// Build a Tantivy `post_time:[lower TO upper]` range clause from
+/// [`crate::commands::ParsedCommand`]'s `after`/`before` named arguments, or
+/// `None` if neither is present. An open endpoint is written as `*`.
+fn build_post_time_range_clause(after: Option<&str>, before: Option<&str>) -> Result<Option<String>> {
+    if after.is_none() && before.is_none() {
+        return Ok(None);
+    }
+    let lower = match after {
+        Some(date) => normalize_date_bound(date, false)?,
+        None => "*".to_string(),
+    };
+    let upper = match before {
+        Some(date) => normalize_date_bound(date, true)?,
+        None => "*".to_string(),
+    };
+    Ok(Some(format!("post_time:[{} TO {}]", lower, upper)))
+}
+
+/// Normalize a `before:`/`after:` date argument to an RFC3339 timestamp
+/// Tantivy's date-field query grammar accepts. A bare `YYYY-MM-DD` date is
+/// anchored to midnight (`end_of_day == false`, for `after:`) or the last
+/// second of that day (`end_of_day == true`, for `before:`); a full RFC3339
+/// timestamp is passed through unchanged.
+fn normalize_date_bound(date: &str, end_of_day: bool) -> Result<String> {
+    if let Ok(naive) = chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d") {
+        let time = if end_of_day {
+            chrono::NaiveTime::from_hms_opt(23, 59, 59).unwrap()
+        } else {
+            chrono::NaiveTime::from_hms_opt(0, 0, 0).unwrap()
+        };
+        Ok(naive
+            .and_time(time)
+            .and_utc()
+            .to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+    } else if chrono::DateTime::parse_from_rfc3339(date).is_ok() {
+        Ok(date.to_string())
+    } else {
+        Err(crate::types::Error::Config(format!(
+            "Invalid date \"{}\", expected YYYY-MM-DD or RFC3339",
+            date
+        )))
+    }
+}
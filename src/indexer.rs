@@ -1,22 +1,46 @@
-//! Full-text search indexer using Tantivy with Chinese tokenization
+//! Full-text search indexer using Tantivy with language-aware tokenization
 //!
 //! This module provides a wrapper around Tantivy for indexing and searching
-//! Telegram messages with support for Chinese word segmentation via jieba.
-
-use crate::types::{Error, IndexMsg, Result, SearchHit, SearchResult};
+//! Telegram messages. Content is tokenized by [`MultiLangTokenizer`], which
+//! detects each piece of text's language (see `crate::lang_detect`) and
+//! dispatches to jieba for Chinese, a script-transition segmenter for
+//! Japanese, or a stemming analyzer otherwise — so mixed-language Telegram
+//! groups get correct segmentation instead of everything going through
+//! jieba. The detected language is also stored in a `lang` field per
+//! document.
+//!
+//! [`Indexer::search`] takes its query straight from Tantivy's own query
+//! grammar rather than a hand-rolled one, since `content` and `sender` are
+//! both registered as its default fields: `sender:Alice "exact phrase"
+//! content:报告 -spam` and `post_time:[2024-01-01T00:00:00Z TO
+//! 2024-06-30T00:00:00Z]` all parse as-is, with `AND`/`OR`/`NOT` combining
+//! clauses the way `QueryParser` already documents. Tantivy's date grammar
+//! only accepts RFC3339 timestamps, so [`normalize_bare_date_ranges`] widens
+//! bare `post_time:[2024-01-01 TO 2024-06-30]`-style dates to RFC3339
+//! (start/end of day) before handing the query to `QueryParser`.
+
+use crate::types::{
+    CorrectedSearchResult, Error, IndexMsg, MediaType, Result, SearchHit, SearchResult,
+};
 use jieba_rs::Jieba;
 use std::collections::HashMap;
 use std::path::Path;
 use std::sync::Arc;
-use std::sync::RwLock;
-use tantivy::collector::TopDocs;
-use tantivy::query::{BooleanQuery, Occur, Query, QueryParser, TermQuery};
+use tantivy::collector::{Count, MultiCollector, TopDocs};
+use tantivy::query::{BooleanQuery, FuzzyTermQuery, Occur, Query, QueryParser, TermQuery};
 use tantivy::schema::*;
 use tantivy::snippet::SnippetGenerator;
 use tantivy::tokenizer::{Token, TokenStream, Tokenizer};
-use tantivy::{Index, IndexReader, IndexWriter, ReloadPolicy, Term, doc};
-
-/// Chinese tokenizer using jieba
+use tantivy::{Index, IndexReader, IndexWriter, ReloadPolicy, Searcher, Term, doc};
+use tokio::sync::mpsc;
+
+/// Chinese tokenizer using jieba's dictionary-based segmentation (HMM
+/// disabled, so out-of-dictionary runs fall back to single characters
+/// rather than being merged into spurious multi-character words) with
+/// byte offsets on every token, so indexing `人人都在说这个人很好` yields
+/// `人人`/`都`/`在`/`说`/`这个`/`人`/`很好` instead of one run per
+/// character — and a query for `人` only matches the standalone `人`
+/// token, not the `人人` compound.
 #[derive(Clone)]
 pub struct ChineseTokenizer {
     jieba: Arc<Jieba>,
@@ -92,23 +116,267 @@ impl TokenStream for ChineseTokenStream<'_> {
     }
 }
 
+/// Token stream for Japanese text: a lightweight script-transition
+/// segmenter in the spirit of TinySegmenter, not the full trained model —
+/// it splits wherever the character class (kanji / hiragana / katakana /
+/// other) changes, which is enough to keep kanji compounds and kana
+/// particles from being indexed as one run.
+pub struct JapaneseTokenStream<'a> {
+    tokens: Vec<Token>,
+    index: usize,
+    _text: &'a str,
+}
+
+#[derive(PartialEq, Eq)]
+enum JapaneseCharClass {
+    Kanji,
+    Hiragana,
+    Katakana,
+    Other,
+}
+
+fn japanese_char_class(c: char) -> JapaneseCharClass {
+    let cp = c as u32;
+    if (0x4E00..=0x9FFF).contains(&cp) || (0x3400..=0x4DBF).contains(&cp) {
+        JapaneseCharClass::Kanji
+    } else if (0x3040..=0x309F).contains(&cp) {
+        JapaneseCharClass::Hiragana
+    } else if (0x30A0..=0x30FF).contains(&cp) {
+        JapaneseCharClass::Katakana
+    } else {
+        JapaneseCharClass::Other
+    }
+}
+
+impl<'a> JapaneseTokenStream<'a> {
+    fn new(text: &'a str) -> Self {
+        let mut tokens = Vec::new();
+        let mut position = 0;
+        let mut chars = text.char_indices().peekable();
+
+        while let Some((start, c)) = chars.next() {
+            if c.is_whitespace() {
+                continue;
+            }
+            let class = japanese_char_class(c);
+            let mut end = start + c.len_utf8();
+            while let Some(&(next_start, next_c)) = chars.peek() {
+                if next_c.is_whitespace() || japanese_char_class(next_c) != class {
+                    break;
+                }
+                end = next_start + next_c.len_utf8();
+                chars.next();
+            }
+            tokens.push(Token {
+                offset_from: start,
+                offset_to: end,
+                position,
+                text: text[start..end].to_string(),
+                position_length: 1,
+            });
+            position += 1;
+        }
+
+        Self {
+            tokens,
+            index: 0,
+            _text: text,
+        }
+    }
+}
+
+impl TokenStream for JapaneseTokenStream<'_> {
+    fn advance(&mut self) -> bool {
+        if self.index < self.tokens.len() {
+            self.index += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn token(&self) -> &Token {
+        &self.tokens[self.index - 1]
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        &mut self.tokens[self.index - 1]
+    }
+}
+
+/// Tokenizer registered on the `content` field in place of a single
+/// hard-wired analyzer. Each call to `token_stream` runs
+/// [`crate::lang_detect::detect`] on the text it's given (index-side
+/// message content, or a query string at search time) and dispatches to
+/// the matching segmentation strategy: jieba for Chinese, the
+/// script-transition segmenter above for Japanese, and a stemming analyzer
+/// (Tantivy's built-in `SimpleTokenizer` + `Stemmer`) for everything else.
+#[derive(Clone)]
+pub struct MultiLangTokenizer {
+    jieba: Arc<Jieba>,
+    latin: tantivy::tokenizer::TextAnalyzer,
+}
+
+impl MultiLangTokenizer {
+    pub fn new() -> Self {
+        let latin = tantivy::tokenizer::TextAnalyzer::builder(
+            tantivy::tokenizer::SimpleTokenizer::default(),
+        )
+        .filter(tantivy::tokenizer::LowerCaser)
+        .filter(tantivy::tokenizer::Stemmer::default())
+        .build();
+
+        Self {
+            jieba: Arc::new(Jieba::new()),
+            latin,
+        }
+    }
+}
+
+impl Default for MultiLangTokenizer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tokenizer for MultiLangTokenizer {
+    type TokenStream<'a> = MultiLangTokenStream<'a>;
+
+    fn token_stream<'a>(&'a mut self, text: &'a str) -> Self::TokenStream<'a> {
+        match crate::lang_detect::detect(text) {
+            crate::lang_detect::Lang::Chinese => {
+                MultiLangTokenStream::Chinese(ChineseTokenStream::new(text, self.jieba.clone()))
+            }
+            crate::lang_detect::Lang::Japanese => {
+                MultiLangTokenStream::Japanese(JapaneseTokenStream::new(text))
+            }
+            crate::lang_detect::Lang::Korean | crate::lang_detect::Lang::Other => {
+                MultiLangTokenStream::Latin(self.latin.token_stream(text))
+            }
+        }
+    }
+}
+
+pub enum MultiLangTokenStream<'a> {
+    Chinese(ChineseTokenStream<'a>),
+    Japanese(JapaneseTokenStream<'a>),
+    Latin(tantivy::tokenizer::BoxTokenStream<'a>),
+}
+
+impl TokenStream for MultiLangTokenStream<'_> {
+    fn advance(&mut self) -> bool {
+        match self {
+            Self::Chinese(s) => s.advance(),
+            Self::Japanese(s) => s.advance(),
+            Self::Latin(s) => s.advance(),
+        }
+    }
+
+    fn token(&self) -> &Token {
+        match self {
+            Self::Chinese(s) => s.token(),
+            Self::Japanese(s) => s.token(),
+            Self::Latin(s) => s.token(),
+        }
+    }
+
+    fn token_mut(&mut self) -> &mut Token {
+        match self {
+            Self::Chinese(s) => s.token_mut(),
+            Self::Japanese(s) => s.token_mut(),
+            Self::Latin(s) => s.token_mut(),
+        }
+    }
+}
+
 /// Indexer for full-text search
 pub struct Indexer {
     index: Index,
-    writer: Arc<RwLock<IndexWriter>>,
     reader: IndexReader,
     fields: IndexFields,
+    writer_tx: mpsc::Sender<WriterOp>,
 }
 
+#[derive(Clone, Copy)]
 struct IndexFields {
     content: Field,
     url: Field,
     chat_id: Field,
     post_time: Field,
     sender: Field,
+    media_type: Field,
+    lang: Field,
+}
+
+/// How many operations the background writer actor will buffer before
+/// `add_document`/`update_document`/`delete_document` calls start blocking.
+const WRITER_CHANNEL_CAPACITY: usize = 1024;
+
+/// Commit after this many operations have accumulated, even if the
+/// debounce window (below) hasn't elapsed yet.
+const WRITER_BATCH_SIZE: usize = 100;
+
+/// Once the first operation of a batch lands, wait up to this long for
+/// more to arrive before committing — trades a little write latency for
+/// turning many small per-message commits (each an fsync) into one.
+const WRITER_DEBOUNCE: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Strictness of a [`Indexer::search`] query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchMode {
+    /// Only the `QueryParser`'s exact term matches count.
+    Exact,
+    /// If the exact match returns fewer than [`FUZZY_RETRY_THRESHOLD`] hits,
+    /// retry with per-token edit-distance matching and also populate
+    /// [`SearchResult::suggestion`] with a "did you mean" rewrite.
+    Fuzzy,
+}
+
+/// Below this many exact hits, a `MatchMode::Fuzzy` search retries with
+/// fuzzy term matching and computes a "did you mean" suggestion.
+const FUZZY_RETRY_THRESHOLD: usize = 3;
+
+/// Maximum Levenshtein edit distance allowed both for the `FuzzyTermQuery`
+/// retry and for picking a "did you mean" replacement term.
+const FUZZY_MAX_DISTANCE: u8 = 2;
+
+/// A queued mutation for the background writer actor (see
+/// [`run_writer_actor`]). Mirrors the mutating `Indexer` methods (`Add`
+/// serves both `add_document` and `update_document`);
+/// `Flush`, `Optimize` and `RebuildChat` are the variants that expect a
+/// reply, so callers that need read-your-writes (tests, `/random`), a
+/// blocking compaction (`/optimize`), or a blocking single-chat refill
+/// (`Indexer::rebuild_chat`) can await them.
+enum WriterOp {
+    /// Upsert by url: deletes any existing document for `IndexMsg::url`
+    /// and adds this one in the same uncommitted transaction. Used by both
+    /// `Indexer::add_document` and `Indexer::update_document`.
+    Add(IndexMsg),
+    Delete(String),
+    DeleteChat(i64),
+    Flush(tokio::sync::oneshot::Sender<Result<()>>),
+    Optimize(tokio::sync::oneshot::Sender<Result<()>>),
+    RebuildChat(i64, Vec<IndexMsg>, tokio::sync::oneshot::Sender<Result<()>>),
 }
 
 impl Indexer {
+    /// Forcibly remove a stale writer lock left behind in `index_dir` by an
+    /// unclean shutdown (process killed rather than given a chance to
+    /// release it), so a subsequent [`Self::new`] on the same directory
+    /// doesn't fail to acquire the lock. Only call this when no other
+    /// process still has the index open — it doesn't check, so using it on
+    /// a live index risks two writers corrupting the same segments. A
+    /// missing lock file is not an error.
+    pub async fn unlock(index_dir: &Path) -> Result<()> {
+        let directory = tantivy::directory::MmapDirectory::open(index_dir)
+            .map_err(|e| Error::Index(e.to_string()))?;
+        match tantivy::Directory::delete(&directory, &tantivy::directory::INDEX_WRITER_LOCK.filepath) {
+            Ok(()) => Ok(()),
+            Err(tantivy::directory::error::DeleteError::FileDoesNotExist(_)) => Ok(()),
+            Err(e) => Err(Error::Index(e.to_string())),
+        }
+    }
+
     /// Create or open an index
     pub async fn new(index_dir: &Path, from_scratch: bool) -> Result<Self> {
         // Create directory if it doesn't exist
@@ -131,10 +399,47 @@ impl Indexer {
                 .map_err(|e| Error::Index(e.to_string()))?
         };
 
-        // Register Chinese tokenizer
+        Self::from_index(index, schema)
+    }
+
+    /// Create or open an index whose on-disk segment files are
+    /// transparently encrypted at rest with a key derived from
+    /// `passphrase` (see `crate::encrypted_dir`). Requires the binary to
+    /// be built with the `encrypted-index` feature.
+    #[cfg(feature = "encrypted-index")]
+    pub async fn new_encrypted(index_dir: &Path, from_scratch: bool, passphrase: &str) -> Result<Self> {
+        tokio::fs::create_dir_all(index_dir).await?;
+
+        let schema = Self::build_schema();
+
+        if from_scratch && index_dir.join("meta.json").exists() {
+            tokio::fs::remove_dir_all(index_dir).await?;
+            tokio::fs::create_dir_all(index_dir).await?;
+        }
+
+        let directory = crate::encrypted_dir::EncryptedDirectory::open(index_dir, passphrase)?;
+        let index = if index_dir.join("meta.json").exists() {
+            Index::open(directory).map_err(|e| Error::Index(e.to_string()))?
+        } else {
+            Index::create(directory, schema.clone(), tantivy::IndexSettings::default())
+                .map_err(|e| Error::Index(e.to_string()))?
+        };
+
+        Self::from_index(index, schema)
+    }
+
+    /// Shared setup once an `Index` (plaintext or encrypted) has been
+    /// opened or created: register the tokenizer and stand up the
+    /// writer/reader/field handles.
+    fn from_index(index: Index, schema: Schema) -> Result<Self> {
+        // Register the Chinese-only tokenizer too, so indexes created
+        // before the multi-lang tokenizer existed still open correctly.
         index
             .tokenizers()
             .register("jieba", ChineseTokenizer::new());
+        index
+            .tokenizers()
+            .register("multi_lang", MultiLangTokenizer::new());
 
         // Create writer with 50MB heap
         let writer = index
@@ -154,25 +459,74 @@ impl Indexer {
             chat_id: schema.get_field("chat_id").unwrap(),
             post_time: schema.get_field("post_time").unwrap(),
             sender: schema.get_field("sender").unwrap(),
+            media_type: schema.get_field("media_type").unwrap(),
+            lang: schema.get_field("lang").unwrap(),
         };
 
+        let (writer_tx, writer_rx) = mpsc::channel(WRITER_CHANNEL_CAPACITY);
+        tokio::spawn(run_writer_actor(
+            writer,
+            reader.clone(),
+            index.clone(),
+            fields,
+            writer_rx,
+        ));
+
         Ok(Self {
             index,
-            writer: Arc::new(RwLock::new(writer)),
             reader,
             fields,
+            writer_tx,
         })
     }
 
+    /// Commit any pending writes and wait for the reader to pick them up.
+    /// Every mutating method only enqueues its operation onto the
+    /// background writer actor (see [`run_writer_actor`]) and returns as
+    /// soon as it's queued, so callers that need read-your-writes (tests,
+    /// `/random`) must call this afterwards.
+    pub async fn flush(&self) -> Result<()> {
+        let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
+        self.send_op(WriterOp::Flush(reply_tx)).await?;
+        reply_rx
+            .await
+            .map_err(|_| Error::Index("writer actor dropped without replying to flush".to_string()))?
+    }
+
+    /// Merge every currently-searchable segment into one and
+    /// garbage-collect the files that merge made obsolete, undoing the
+    /// segment fragmentation that repeated commits cause. This
+    /// speeds up `search` and the full-scan methods below
+    /// (`list_indexed_chats`, `get_chat_document_counts`,
+    /// `retrieve_random_document`), which all iterate `segment_readers()`.
+    /// Mirrors tantivy's own `merge` CLI subcommand. Exposed so an admin
+    /// command can run it periodically; blocks until the merge, garbage
+    /// collection, and reader reload have all completed.
+    pub async fn optimize(&self) -> Result<()> {
+        let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
+        self.send_op(WriterOp::Optimize(reply_tx)).await?;
+        reply_rx.await.map_err(|_| {
+            Error::Index("writer actor dropped without replying to optimize".to_string())
+        })?
+    }
+
+    /// Enqueue `op` onto the background writer actor.
+    async fn send_op(&self, op: WriterOp) -> Result<()> {
+        self.writer_tx
+            .send(op)
+            .await
+            .map_err(|_| Error::Index("writer actor task is no longer running".to_string()))
+    }
+
     /// Build Tantivy schema matching Python's Whoosh schema
     fn build_schema() -> Schema {
         let mut schema_builder = Schema::builder();
 
-        // content: TEXT with Chinese analyzer, stored
+        // content: TEXT with the multi-lang analyzer, stored
         let text_options = TextOptions::default()
             .set_indexing_options(
                 TextFieldIndexing::default()
-                    .set_tokenizer("jieba")
+                    .set_tokenizer("multi_lang")
                     .set_index_option(IndexRecordOption::WithFreqsAndPositions),
             )
             .set_stored();
@@ -181,240 +535,253 @@ impl Indexer {
         // url: ID (STRING), stored, indexed (for unique lookups)
         schema_builder.add_text_field("url", STRING | STORED);
 
-        // chat_id: i64, stored, indexed (for filtering)
-        schema_builder.add_i64_field("chat_id", INDEXED | STORED);
+        // chat_id: i64, stored, indexed (for filtering) and fast (for the
+        // columnar per-chat aggregation in `list_indexed_chats` /
+        // `get_chat_document_counts`)
+        schema_builder.add_i64_field("chat_id", INDEXED | STORED | FAST);
 
         // post_time: DATETIME, stored, indexed, fast (for sorting)
         schema_builder.add_date_field("post_time", INDEXED | STORED | FAST);
 
-        // sender: TEXT, stored
-        schema_builder.add_text_field("sender", STORED);
+        // sender: TEXT, stored and indexed (with the default tokenizer) so
+        // it can be searched directly (`sender:Alice`) or as one of the
+        // default fields for an unqualified query
+        schema_builder.add_text_field("sender", TEXT | STORED);
+
+        // media_type: exact-match keyword, stored (for future type filtering)
+        schema_builder.add_text_field("media_type", STRING | STORED);
+
+        // lang: detected language code (e.g. "zh", "ja", "other"), exact-match,
+        // stored and indexed so it could be filtered/faceted on later
+        schema_builder.add_text_field("lang", STRING | STORED);
 
         schema_builder.build()
     }
 
-    /// Add a document to the index
+    /// Enqueue a document to be added to the index. Returns as soon as the
+    /// operation is queued; call [`Self::flush`] afterwards for
+    /// read-your-writes.
     pub async fn add_document(&self, msg: IndexMsg) -> Result<()> {
-        // Deduplicate by URL (Telegram message ID is encoded in the URL).
-        // Tantivy doesn't enforce uniqueness, so we explicitly delete any existing doc first.
-        let url_term = Term::from_field_text(self.fields.url, &msg.url);
-        let doc = doc!(
-            self.fields.content => msg.content,
-            self.fields.url => msg.url,
-            self.fields.chat_id => msg.chat_id,
-            self.fields.post_time => tantivy::DateTime::from_timestamp_secs(msg.post_time.timestamp()),
-            self.fields.sender => msg.sender,
-        );
-
-        let mut writer = self.writer.write().unwrap();
-        writer.delete_term(url_term);
-        writer
-            .add_document(doc)
-            .map_err(|e| Error::Index(e.to_string()))?;
-        writer.commit().map_err(|e| Error::Index(e.to_string()))?;
-
-        // Reload reader to see changes
-        self.reader
-            .reload()
-            .map_err(|e| Error::Index(e.to_string()))?;
-
-        Ok(())
+        self.send_op(WriterOp::Add(msg)).await
     }
 
-    /// Add multiple documents in batch (much faster than individual adds)
+    /// Enqueue multiple documents to be added (deduplicating by URL within
+    /// the batch, keeping the last occurrence). Returns as soon as all
+    /// operations are queued; call [`Self::flush`] afterwards for
+    /// read-your-writes.
     pub async fn add_documents_batch(&self, msgs: Vec<IndexMsg>) -> Result<()> {
         if msgs.is_empty() {
             return Ok(());
         }
 
-        let mut writer = self.writer.write().unwrap();
-
-        // Deduplicate by URL within the batch as well (keep the last occurrence).
         let mut by_url: HashMap<String, IndexMsg> = HashMap::new();
         for msg in msgs {
             by_url.insert(msg.url.clone(), msg);
         }
 
         for (_, msg) in by_url {
-            writer.delete_term(Term::from_field_text(self.fields.url, &msg.url));
-            let doc = doc!(
-                self.fields.content => msg.content,
-                self.fields.url => msg.url,
-                self.fields.chat_id => msg.chat_id,
-                self.fields.post_time => tantivy::DateTime::from_timestamp_secs(msg.post_time.timestamp()),
-                self.fields.sender => msg.sender,
-            );
-            writer
-                .add_document(doc)
-                .map_err(|e| Error::Index(e.to_string()))?;
+            self.send_op(WriterOp::Add(msg)).await?;
         }
 
-        // Commit once for all documents
-        writer.commit().map_err(|e| Error::Index(e.to_string()))?;
-
-        // Reload reader to see changes
-        self.reader
-            .reload()
-            .map_err(|e| Error::Index(e.to_string()))?;
-
         Ok(())
     }
 
-    /// Update a document in the index
-    pub async fn update_document(&self, url: &str, content: &str) -> Result<()> {
-        let searcher = self.reader.searcher();
-
-        // Find existing document by URL
-        let url_term = Term::from_field_text(self.fields.url, url);
-        let url_query = TermQuery::new(url_term.clone(), IndexRecordOption::Basic);
-
-        let top_docs = searcher
-            .search(&url_query, &TopDocs::with_limit(1))
-            .map_err(|e| Error::Index(e.to_string()))?;
-
-        if let Some((_, doc_address)) = top_docs.first() {
-            let doc: tantivy::TantivyDocument = searcher
-                .doc(*doc_address)
-                .map_err(|e| Error::Index(e.to_string()))?;
-
-            // Extract existing fields
-            let chat_id = doc
-                .get_first(self.fields.chat_id)
-                .and_then(|v| v.as_i64())
-                .unwrap_or(0);
-            let post_time = doc
-                .get_first(self.fields.post_time)
-                .and_then(|v| v.as_datetime())
-                .unwrap_or(tantivy::DateTime::from_timestamp_secs(0));
-            let sender = doc
-                .get_first(self.fields.sender)
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-
-            // Create updated document
-            let updated_doc = doc!(
-                self.fields.content => content,
-                self.fields.url => url,
-                self.fields.chat_id => chat_id,
-                self.fields.post_time => post_time,
-                self.fields.sender => sender,
-            );
-
-            // Delete old and add new
-            let mut writer = self.writer.write().unwrap();
-            writer.delete_term(url_term);
-            writer
-                .add_document(updated_doc)
-                .map_err(|e| Error::Index(e.to_string()))?;
-            writer.commit().map_err(|e| Error::Index(e.to_string()))?;
-
-            // Reload reader to see changes
-            self.reader
-                .reload()
-                .map_err(|e| Error::Index(e.to_string()))?;
-        }
-
-        Ok(())
+    /// Enqueue an atomic upsert of an edited message: the old document for
+    /// `msg.url` (if any) is deleted and `msg` added in the same
+    /// uncommitted writer transaction, so whichever commit picks this op up
+    /// can never expose a state with both, neither, or a duplicate. Just
+    /// [`Self::add_document`] under another name — kept separate so call
+    /// sites can say which they mean — since an upsert-by-url is exactly
+    /// what `Add` already does. Returns as soon as the operation is queued;
+    /// call [`Self::flush`] afterwards for read-your-writes.
+    pub async fn update_document(&self, msg: IndexMsg) -> Result<()> {
+        self.add_document(msg).await
     }
 
-    /// Delete a document from the index
+    /// Enqueue the removal of a document from the index. Returns as soon
+    /// as the operation is queued; call [`Self::flush`] afterwards for
+    /// read-your-writes.
     pub async fn delete_document(&self, url: &str) -> Result<()> {
-        let term = Term::from_field_text(self.fields.url, url);
-        let mut writer = self.writer.write().unwrap();
-        writer.delete_term(term);
-        writer.commit().map_err(|e| Error::Index(e.to_string()))?;
-
-        // Reload reader to see changes
-        self.reader
-            .reload()
-            .map_err(|e| Error::Index(e.to_string()))?;
-
-        Ok(())
+        self.send_op(WriterOp::Delete(url.to_string())).await
     }
 
-    /// Delete all documents for a specific chat
+    /// Enqueue the removal of all documents for a specific chat. Returns
+    /// as soon as the operation is queued; call [`Self::flush`] afterwards
+    /// for read-your-writes.
     pub async fn delete_chat_documents(&self, chat_id: i64) -> Result<()> {
-        let term = Term::from_field_i64(self.fields.chat_id, chat_id);
-        let mut writer = self.writer.write().unwrap();
-
-        // Delete all documents matching this chat_id
-        writer.delete_term(term);
-        writer.commit().map_err(|e| Error::Index(e.to_string()))?;
-
-        // Reload reader to see changes
-        self.reader
-            .reload()
-            .map_err(|e| Error::Index(e.to_string()))?;
+        self.send_op(WriterOp::DeleteChat(chat_id)).await
+    }
 
-        Ok(())
+    /// Refill a single chat's documents in one committed batch: every
+    /// existing document for `chat_id` is deleted and `messages` added in
+    /// the same writer transaction, so a caller recovering from a partial
+    /// or corrupted download for one chat can redo just that chat without
+    /// disturbing any other. Blocks until committed and searchable, unlike
+    /// the other mutating methods above.
+    pub async fn rebuild_chat(&self, chat_id: i64, messages: Vec<IndexMsg>) -> Result<()> {
+        let (reply_tx, reply_rx) = tokio::sync::oneshot::channel();
+        self.send_op(WriterOp::RebuildChat(chat_id, messages, reply_tx))
+            .await?;
+        reply_rx.await.map_err(|_| {
+            Error::Index("writer actor dropped without replying to rebuild_chat".to_string())
+        })?
     }
 
-    /// Search the index
+    /// Search the index. `query_str` is first widened by
+    /// [`normalize_bare_date_ranges`] (so bare `post_time:[2024-01-01 TO
+    /// 2024-06-30]` dates work, not just RFC3339), then parsed by Tantivy's
+    /// own query grammar against `content` and `sender` as default fields,
+    /// so `sender:Alice "exact phrase" content:报告 -spam` and
+    /// `post_time:[2024-01-01T00:00:00Z TO 2024-06-30T00:00:00Z]` work as
+    /// field filters, phrases, negation and date ranges without any extra
+    /// parsing here. `match_mode`
+    /// controls what happens when the exact query comes up short:
+    /// `MatchMode::Fuzzy` retries with per-token `FuzzyTermQuery` matching
+    /// and populates
+    /// [`SearchResult::suggestion`][crate::types::SearchResult::suggestion]
+    /// with a "did you mean" rewrite built from the `content` and `sender`
+    /// term dictionaries. Hits are ranked by Tantivy's BM25 scorer (highest first,
+    /// exposed as [`SearchHit::score`][crate::types::SearchHit::score])
+    /// rather than insertion order.
     pub async fn search(
         &self,
         query_str: &str,
         in_chats: Option<&[i64]>,
         page_len: usize,
         page_num: usize,
+        match_mode: MatchMode,
     ) -> Result<SearchResult> {
         let searcher = self.reader.searcher();
+        let query_str = normalize_bare_date_ranges(query_str);
 
-        // Parse query for content field
-        let query_parser = QueryParser::for_index(&self.index, vec![self.fields.content]);
-        let mut query = query_parser
-            .parse_query(query_str)
+        let query_parser =
+            QueryParser::for_index(&self.index, vec![self.fields.content, self.fields.sender]);
+        let content_query = query_parser
+            .parse_query(&query_str)
             .map_err(|e| Error::Index(e.to_string()))?;
 
-        // Add chat filter if specified
-        if let Some(chats) = in_chats {
-            let chat_queries: Vec<(Occur, Box<dyn Query>)> = chats
-                .iter()
-                .map(|&chat_id| {
-                    let term = Term::from_field_i64(self.fields.chat_id, chat_id);
-                    let query: Box<dyn Query> =
-                        Box::new(TermQuery::new(term, IndexRecordOption::Basic));
-                    (Occur::Should, query)
-                })
-                .collect();
+        let mut result = self.run_search(
+            &searcher,
+            content_query.box_clone(),
+            in_chats,
+            page_len,
+            page_num,
+        )?;
+
+        if match_mode == MatchMode::Fuzzy && result.total_results < FUZZY_RETRY_THRESHOLD {
+            if let Some(fuzzy_query) = self.build_fuzzy_query(&*content_query) {
+                let fuzzy_result =
+                    self.run_search(&searcher, fuzzy_query, in_chats, page_len, page_num)?;
+                if fuzzy_result.total_results > result.total_results {
+                    result = fuzzy_result;
+                }
+            }
+            result.suggestion = self.suggest_correction(&searcher, &query_str)?;
+        }
+
+        Ok(result)
+    }
+
+    /// Like [`Self::search`], but always reports the original query's hit
+    /// count alongside a term-dictionary spelling suggestion, and re-runs
+    /// the search with that suggestion when it differs from `query_str`.
+    /// Useful for a caller that wants to show "Showing results for …"
+    /// rather than (or in addition to) a bare suggestion string.
+    pub async fn search_with_correction(
+        &self,
+        query_str: &str,
+        in_chats: Option<&[i64]>,
+        page_len: usize,
+        page_num: usize,
+    ) -> Result<CorrectedSearchResult> {
+        let searcher = self.reader.searcher();
+        let query_str = normalize_bare_date_ranges(query_str);
 
-            let chat_filter = BooleanQuery::new(chat_queries);
+        let query_parser =
+            QueryParser::for_index(&self.index, vec![self.fields.content, self.fields.sender]);
+        let content_query = query_parser
+            .parse_query(&query_str)
+            .map_err(|e| Error::Index(e.to_string()))?;
+
+        let original = self.run_search(&searcher, content_query, in_chats, page_len, page_num)?;
+        let original_total_results = original.total_results;
 
-            // Combine content query with chat filter
-            let combined_query = BooleanQuery::new(vec![
-                (Occur::Must, Box::new(query)),
-                (Occur::Must, Box::new(chat_filter)),
-            ]);
-            query = Box::new(combined_query);
+        if original_total_results >= FUZZY_RETRY_THRESHOLD {
+            return Ok(CorrectedSearchResult {
+                original_total_results,
+                suggestion: None,
+                result: original,
+            });
         }
 
+        let suggestion = self.suggest_correction(&searcher, &query_str)?;
+        let result = match &suggestion {
+            Some(corrected) if corrected != &query_str => {
+                let corrected_query = query_parser
+                    .parse_query(corrected)
+                    .map_err(|e| Error::Index(e.to_string()))?;
+                self.run_search(&searcher, corrected_query, in_chats, page_len, page_num)?
+            }
+            _ => original,
+        };
+
+        Ok(CorrectedSearchResult {
+            original_total_results,
+            suggestion,
+            result,
+        })
+    }
+
+    /// Run `content_query` (not yet chat-filtered) against the index and
+    /// build a [`SearchResult`], including BM25 scores and highlighted
+    /// snippets, ranked most relevant first. Shared by the exact query in
+    /// [`Self::search`] and its `MatchMode::Fuzzy` retry, which only differ
+    /// in which query they pass in.
+    fn run_search(
+        &self,
+        searcher: &Searcher,
+        content_query: Box<dyn Query>,
+        in_chats: Option<&[i64]>,
+        page_len: usize,
+        page_num: usize,
+    ) -> Result<SearchResult> {
+        let query = self.apply_chat_filter(content_query, in_chats);
+
         // Calculate offset
         let offset = (page_num - 1) * page_len;
 
-        // Search with sorting by post_time descending
-        let collector = TopDocs::with_limit(page_len)
-            .and_offset(offset)
-            .order_by_fast_field::<tantivy::DateTime>("post_time", tantivy::Order::Desc);
+        // Rank by Tantivy's default BM25 scorer, most relevant first, so
+        // results order by how well they match rather than by insertion
+        // order.
+        let collector = TopDocs::with_limit(page_len).and_offset(offset);
 
-        let top_docs = searcher
-            .search(&query, &collector)
-            .map_err(|e| Error::Index(e.to_string()))?;
+        // Run the top-docs and total-count collectors in a single pass over
+        // the searcher instead of querying twice.
+        let mut multi_collector = MultiCollector::new();
+        let count_handle = multi_collector.add_collector(Count);
+        let top_docs_handle = multi_collector.add_collector(collector);
 
-        // Get total count
-        let count_collector = tantivy::collector::Count;
-        let total_results = searcher
-            .search(&query, &count_collector)
+        let mut fruits = searcher
+            .search(&query, &multi_collector)
             .map_err(|e| Error::Index(e.to_string()))?;
-
-        // Create snippet generator for highlighting
-        let mut snippet_generator =
-            SnippetGenerator::create(&searcher, &*query, self.fields.content)
+        let total_results = count_handle.extract(&mut fruits);
+        let top_docs = top_docs_handle.extract(&mut fruits);
+
+        // Create snippet generators for highlighting. A query can match via
+        // either default field (`content` or `sender`), so both are tried
+        // per hit and whichever one actually produced a highlight wins.
+        let mut content_snippet_generator =
+            SnippetGenerator::create(searcher, &*query, self.fields.content)
                 .map_err(|e| Error::Index(e.to_string()))?;
-        snippet_generator.set_max_num_chars(100);
+        content_snippet_generator.set_max_num_chars(100);
+        let mut sender_snippet_generator =
+            SnippetGenerator::create(searcher, &*query, self.fields.sender)
+                .map_err(|e| Error::Index(e.to_string()))?;
+        sender_snippet_generator.set_max_num_chars(100);
 
         // Convert results to SearchHits
         let mut hits = Vec::new();
-        for (_score, doc_address) in top_docs {
+        for (score, doc_address) in top_docs {
             let doc: tantivy::TantivyDocument = searcher
                 .doc(doc_address)
                 .map_err(|e| Error::Index(e.to_string()))?;
@@ -446,6 +813,11 @@ impl Indexer {
                 .and_then(|v| v.as_str())
                 .unwrap_or("")
                 .to_string();
+            let media_type = doc
+                .get_first(self.fields.media_type)
+                .and_then(|v| v.as_str())
+                .map(MediaType::parse)
+                .unwrap_or(MediaType::Text);
 
             let msg = IndexMsg {
                 content: content.clone(),
@@ -453,13 +825,29 @@ impl Indexer {
                 chat_id,
                 post_time,
                 sender,
+                media_type,
             };
 
-            // Generate highlighted snippet
-            let snippet = snippet_generator.snippet_from_doc(&doc);
-            let highlighted = snippet.to_html();
+            // Generate highlighted snippet, following whichever field the
+            // query actually matched on. `content` is tried first since
+            // that's what's shown to the user; if it came up with no
+            // highlight (the hit matched only on `sender`), fall back to
+            // the sender snippet so a `sender:Alice` search still shows
+            // something highlighted.
+            let content_snippet = content_snippet_generator.snippet_from_doc(&doc);
+            let content_html = content_snippet.to_html();
+            let highlighted = if content_html.contains("<b>") {
+                add_snippet_ellipses(&content_html, content_snippet.fragment(), &content)
+            } else {
+                let sender_html = sender_snippet_generator.snippet_from_doc(&doc).to_html();
+                if sender_html.contains("<b>") {
+                    sender_html
+                } else {
+                    content_html
+                }
+            };
 
-            hits.push(SearchHit { msg, highlighted });
+            hits.push(SearchHit { msg, highlighted, score });
         }
 
         let is_last_page = offset + page_len >= total_results;
@@ -468,25 +856,178 @@ impl Indexer {
             hits,
             is_last_page,
             total_results,
+            suggestion: None,
         })
     }
 
-    /// List all indexed chat IDs
+    /// Wrap `content_query` in a `Must` boolean query alongside a
+    /// `chat_id` filter restricted to `in_chats`, or return it unchanged
+    /// when no chat filter applies.
+    fn apply_chat_filter(
+        &self,
+        content_query: Box<dyn Query>,
+        in_chats: Option<&[i64]>,
+    ) -> Box<dyn Query> {
+        let Some(chats) = in_chats else {
+            return content_query;
+        };
+
+        let chat_queries: Vec<(Occur, Box<dyn Query>)> = chats
+            .iter()
+            .map(|&chat_id| {
+                let term = Term::from_field_i64(self.fields.chat_id, chat_id);
+                let query: Box<dyn Query> = Box::new(TermQuery::new(term, IndexRecordOption::Basic));
+                (Occur::Should, query)
+            })
+            .collect();
+        let chat_filter = BooleanQuery::new(chat_queries);
+
+        Box::new(BooleanQuery::new(vec![
+            (Occur::Must, content_query),
+            (Occur::Must, Box::new(chat_filter)),
+        ]))
+    }
+
+    /// Build a fuzzy retry of `content_query`: a `FuzzyTermQuery` (edit
+    /// distance up to [`FUZZY_MAX_DISTANCE`]) per `content`/`sender` term
+    /// the exact query matched on, combined under `Occur::Should` so a
+    /// message within editing distance of any of them counts as a hit.
+    /// Returns `None` if the exact query matched no term on either field
+    /// (e.g. a bare wildcard).
+    fn build_fuzzy_query(&self, content_query: &dyn Query) -> Option<Box<dyn Query>> {
+        let mut terms = Vec::new();
+        content_query.query_terms(&mut |term, _positions_required| {
+            if term.field() == self.fields.content || term.field() == self.fields.sender {
+                terms.push(term.clone());
+            }
+        });
+        if terms.is_empty() {
+            return None;
+        }
+
+        let fuzzy_queries: Vec<(Occur, Box<dyn Query>)> = terms
+            .into_iter()
+            .map(|term| {
+                let query: Box<dyn Query> =
+                    Box::new(FuzzyTermQuery::new(term, FUZZY_MAX_DISTANCE, true));
+                (Occur::Should, query)
+            })
+            .collect();
+
+        Some(Box::new(BooleanQuery::new(fuzzy_queries)))
+    }
+
+    /// Build a "did you mean" suggestion for `query_str` by replacing each
+    /// of its tokens (as segmented by the content field's `multi_lang`
+    /// tokenizer) with the closest term in the `content`/`sender` term
+    /// dictionaries (see [`Self::closest_indexed_term`]), when the token
+    /// itself isn't indexed. Returns `None` when
+    /// no token has a close enough replacement, i.e. there's nothing to
+    /// suggest.
+    fn suggest_correction(&self, searcher: &Searcher, query_str: &str) -> Result<Option<String>> {
+        let mut tokenizer = self
+            .index
+            .tokenizers()
+            .get("multi_lang")
+            .expect("multi_lang tokenizer is registered in from_index");
+        let mut token_stream = tokenizer.token_stream(query_str);
+
+        let mut tokens = Vec::new();
+        while token_stream.advance() {
+            tokens.push(token_stream.token().text.clone());
+        }
+        if tokens.is_empty() {
+            return Ok(None);
+        }
+
+        let mut changed = false;
+        let mut suggested = Vec::with_capacity(tokens.len());
+        for token in tokens {
+            match self.closest_indexed_term(searcher, &token)? {
+                Some(replacement) if replacement != token => {
+                    changed = true;
+                    suggested.push(replacement);
+                }
+                _ => suggested.push(token),
+            }
+        }
+
+        Ok(changed.then(|| suggested.join(" ")))
+    }
+
+    /// Scan every segment's `content`/`sender` term dictionaries for the
+    /// term closest to `token` by [`crate::utils::levenshtein_distance`],
+    /// breaking ties between equally-distant candidates in favor of the one
+    /// with the higher document frequency (a common term is a more likely
+    /// intended word than a rare one). Returns `token` itself unchanged as
+    /// soon as it's found verbatim in either dictionary, the nearest term
+    /// within [`FUZZY_MAX_DISTANCE`] edits otherwise, or `None` if nothing
+    /// is close enough.
+    fn closest_indexed_term(&self, searcher: &Searcher, token: &str) -> Result<Option<String>> {
+        // (term, distance, doc_freq)
+        let mut best: Option<(String, usize, u64)> = None;
+
+        for field in [self.fields.content, self.fields.sender] {
+            for segment_reader in searcher.segment_readers() {
+                let inverted_index = segment_reader
+                    .inverted_index(field)
+                    .map_err(|e| Error::Index(e.to_string()))?;
+                let term_dict = inverted_index.terms();
+                let mut stream = term_dict
+                    .stream()
+                    .map_err(|e| Error::Index(e.to_string()))?;
+
+                while let Some(term_bytes) = stream.next() {
+                    let Ok(term) = std::str::from_utf8(term_bytes) else {
+                        continue;
+                    };
+                    if term == token {
+                        return Ok(Some(token.to_string()));
+                    }
+
+                    let distance = crate::utils::levenshtein_distance(token, term);
+                    if distance > FUZZY_MAX_DISTANCE as usize {
+                        continue;
+                    }
+                    let doc_freq = u64::from(stream.value().doc_freq);
+                    let is_better = match &best {
+                        None => true,
+                        Some((_, best_distance, best_doc_freq)) => {
+                            distance < *best_distance
+                                || (distance == *best_distance && doc_freq > *best_doc_freq)
+                        }
+                    };
+                    if is_better {
+                        best = Some((term.to_string(), distance, doc_freq));
+                    }
+                }
+            }
+        }
+
+        Ok(best.map(|(term, _, _)| term))
+    }
+
+    /// Number of documents currently in the index, for metrics/status reporting.
+    pub async fn num_docs(&self) -> Result<usize> {
+        let searcher = self.reader.searcher();
+        Ok(searcher.num_docs() as usize)
+    }
+
+    /// List all indexed chat IDs. Reads the `chat_id` fast-field column
+    /// directly instead of deserializing every stored document, so this
+    /// stays cheap even on indices with millions of messages.
     pub async fn list_indexed_chats(&self) -> Result<Vec<i64>> {
         let searcher = self.reader.searcher();
         let mut chat_ids = std::collections::HashSet::new();
 
-        // Iterate through all documents and collect unique chat_ids
         for segment_reader in searcher.segment_readers() {
-            let store_reader = segment_reader
-                .get_store_reader(0)
+            let chat_id_column = segment_reader
+                .fast_fields()
+                .i64("chat_id")
                 .map_err(|e| Error::Index(e.to_string()))?;
 
             for doc_id in 0..segment_reader.max_doc() {
-                if let Ok(doc) = store_reader.get::<tantivy::TantivyDocument>(doc_id)
-                    && let Some(chat_id_value) = doc.get_first(self.fields.chat_id)
-                    && let Some(chat_id) = chat_id_value.as_i64()
-                {
+                if let Some(chat_id) = chat_id_column.first(doc_id) {
                     chat_ids.insert(chat_id);
                 }
             }
@@ -495,23 +1036,23 @@ impl Indexer {
         Ok(chat_ids.into_iter().collect())
     }
 
-    /// Get document counts per chat (efficient single-pass counting)
+    /// Get document counts per chat. Like [`Self::list_indexed_chats`],
+    /// this reads the `chat_id` fast-field column directly rather than
+    /// the stored documents, turning a full store scan into a columnar
+    /// pass.
     /// Returns a HashMap of chat_id -> document_count
     pub async fn get_chat_document_counts(&self) -> Result<std::collections::HashMap<i64, usize>> {
         let searcher = self.reader.searcher();
         let mut counts: std::collections::HashMap<i64, usize> = std::collections::HashMap::new();
 
-        // Iterate through all documents and count by chat_id
         for segment_reader in searcher.segment_readers() {
-            let store_reader = segment_reader
-                .get_store_reader(0)
+            let chat_id_column = segment_reader
+                .fast_fields()
+                .i64("chat_id")
                 .map_err(|e| Error::Index(e.to_string()))?;
 
             for doc_id in 0..segment_reader.max_doc() {
-                if let Ok(doc) = store_reader.get::<tantivy::TantivyDocument>(doc_id)
-                    && let Some(chat_id_value) = doc.get_first(self.fields.chat_id)
-                    && let Some(chat_id) = chat_id_value.as_i64()
-                {
+                if let Some(chat_id) = chat_id_column.first(doc_id) {
                     *counts.entry(chat_id).or_insert(0) += 1;
                 }
             }
@@ -520,6 +1061,34 @@ impl Indexer {
         Ok(counts)
     }
 
+    /// Find the highest indexed message id for a chat, parsing it from the
+    /// last path segment of the stored message key (see
+    /// [`crate::utils::build_message_key`]). Returns `None` when the chat
+    /// has no indexed documents. Used by incremental `/download_chat` to fetch
+    /// only messages newer than what is already indexed.
+    pub async fn max_msg_id_for_chat(&self, chat_id: i64) -> Result<Option<i32>> {
+        let searcher = self.reader.searcher();
+        let mut max_id: Option<i32> = None;
+
+        for segment_reader in searcher.segment_readers() {
+            let store_reader = segment_reader
+                .get_store_reader(0)
+                .map_err(|e| Error::Index(e.to_string()))?;
+
+            for doc_id in 0..segment_reader.max_doc() {
+                if let Ok(doc) = store_reader.get::<tantivy::TantivyDocument>(doc_id)
+                    && doc.get_first(self.fields.chat_id).and_then(|v| v.as_i64()) == Some(chat_id)
+                    && let Some(url) = doc.get_first(self.fields.url).and_then(|v| v.as_str())
+                    && let Some(msg_id) = url.rsplit('/').next().and_then(|s| s.parse::<i32>().ok())
+                {
+                    max_id = Some(max_id.map_or(msg_id, |m| m.max(msg_id)));
+                }
+            }
+        }
+
+        Ok(max_id)
+    }
+
     /// Retrieve a random document (for /random command)
     pub async fn retrieve_random_document(&self) -> Result<Option<IndexMsg>> {
         let searcher = self.reader.searcher();
@@ -574,6 +1143,11 @@ impl Indexer {
             .and_then(|v| v.as_str())
             .unwrap_or("")
             .to_string();
+        let media_type = doc
+            .get_first(self.fields.media_type)
+            .and_then(|v| v.as_str())
+            .map(MediaType::parse)
+            .unwrap_or(MediaType::Text);
 
         Ok(Some(IndexMsg {
             content,
@@ -581,10 +1155,245 @@ impl Indexer {
             chat_id,
             post_time,
             sender,
+            media_type,
         }))
     }
 }
 
+/// Widen bare `YYYY-MM-DD` dates inside `post_time:[... TO ...]`/`{... TO
+/// ...}` range clauses to RFC3339, since Tantivy's query grammar only
+/// accepts RFC3339 timestamps as date-range bounds. The lower bound becomes
+/// midnight and the upper bound 23:59:59 of that day, so `post_time:[2024-01-01
+/// TO 2024-06-30]` covers the same days a user would expect from the bare
+/// form. Bounds that already parse as RFC3339 (or are `*`) pass through
+/// unchanged; anything else is left as-is for `QueryParser` to reject.
+static POST_TIME_RANGE_RE: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
+    regex::Regex::new(r"post_time:([\[{])\s*(\S+)\s+TO\s+(\S+)\s*([\]}])").unwrap()
+});
+
+fn normalize_bare_date_ranges(query_str: &str) -> String {
+    POST_TIME_RANGE_RE
+        .replace_all(query_str, |caps: &regex::Captures| {
+            let lower = normalize_date_bound(&caps[2], false);
+            let upper = normalize_date_bound(&caps[3], true);
+            format!("post_time:{}{} TO {}{}", &caps[1], lower, upper, &caps[4])
+        })
+        .into_owned()
+}
+
+/// Normalize a single `post_time` range bound: a bare `YYYY-MM-DD` date
+/// becomes midnight (`end_of_day` false) or 23:59:59 (`end_of_day` true)
+/// UTC on that day; `*` and anything already RFC3339 pass through unchanged.
+fn normalize_date_bound(bound: &str, end_of_day: bool) -> String {
+    if bound == "*" || chrono::DateTime::parse_from_rfc3339(bound).is_ok() {
+        return bound.to_string();
+    }
+    let Ok(date) = chrono::NaiveDate::parse_from_str(bound, "%Y-%m-%d") else {
+        return bound.to_string();
+    };
+    let time = if end_of_day {
+        chrono::NaiveTime::from_hms_opt(23, 59, 59).unwrap()
+    } else {
+        chrono::NaiveTime::from_hms_opt(0, 0, 0).unwrap()
+    };
+    date.and_time(time).and_utc().to_rfc3339()
+}
+
+/// Prefix/suffix `snippet_html` (already `<b>`-highlighted by a
+/// `SnippetGenerator`, which itself picks the smallest window scoring
+/// highest for distinct matched terms) with an ellipsis wherever
+/// `fragment` — the plain-text window the snippet was built from — doesn't
+/// reach the start or end of the full `content`, so a preview out of a long
+/// message reads as a preview rather than a truncated sentence.
+fn add_snippet_ellipses(snippet_html: &str, fragment: &str, content: &str) -> String {
+    if fragment.is_empty() {
+        return snippet_html.to_string();
+    }
+    let Some(start) = content.find(fragment) else {
+        return snippet_html.to_string();
+    };
+    let end = start + fragment.len();
+
+    let mut result = String::new();
+    if start > 0 {
+        result.push_str("… ");
+    }
+    result.push_str(snippet_html);
+    if end < content.len() {
+        result.push_str(" …");
+    }
+    result
+}
+
+/// Commit `writer`'s pending changes and block the reader onto them.
+fn commit_and_reload(writer: &mut IndexWriter, reader: &IndexReader) -> Result<()> {
+    writer.commit().map_err(|e| Error::Index(e.to_string()))?;
+    reader.reload().map_err(|e| Error::Index(e.to_string()))?;
+    Ok(())
+}
+
+/// Commit any pending writes, merge every currently-searchable segment
+/// into one, garbage-collect the files that merge made obsolete, and
+/// reload `reader` onto the result. This is the `Indexer::optimize` body;
+/// see that method's doc comment for why.
+async fn merge_segments(writer: &mut IndexWriter, index: &Index, reader: &IndexReader) -> Result<()> {
+    commit_and_reload(writer, reader)?;
+
+    let segment_ids = index
+        .searchable_segment_ids()
+        .map_err(|e| Error::Index(e.to_string()))?;
+    if segment_ids.len() > 1 {
+        writer
+            .merge(&segment_ids)
+            .await
+            .map_err(|e| Error::Index(e.to_string()))?;
+    }
+
+    writer
+        .garbage_collect_files()
+        .await
+        .map_err(|e| Error::Index(e.to_string()))?;
+    reader.reload().map_err(|e| Error::Index(e.to_string()))?;
+    Ok(())
+}
+
+/// Delete every document for `chat_id` and stage `messages` in its place
+/// (deduplicating by URL, keeping the last occurrence, same as
+/// [`Indexer::add_documents_batch`]), then commit the whole thing as one
+/// batch. This is the `Indexer::rebuild_chat` body; see that method's doc
+/// comment for why.
+fn rebuild_chat(
+    writer: &mut IndexWriter,
+    reader: &IndexReader,
+    fields: &IndexFields,
+    chat_id: i64,
+    messages: Vec<IndexMsg>,
+) -> Result<()> {
+    writer.delete_term(Term::from_field_i64(fields.chat_id, chat_id));
+
+    let mut by_url: HashMap<String, IndexMsg> = HashMap::new();
+    for msg in messages {
+        by_url.insert(msg.url.clone(), msg);
+    }
+    for (_, msg) in by_url {
+        apply_write(writer, fields, WriterOp::Add(msg));
+    }
+
+    commit_and_reload(writer, reader)
+}
+
+/// Stage `op` on `writer` (delete_term + add_document as needed) without
+/// committing. `Flush`, `Optimize` and `RebuildChat` are handled by the
+/// caller before this is reached.
+fn apply_write(writer: &mut IndexWriter, fields: &IndexFields, op: WriterOp) {
+    match op {
+        WriterOp::Add(msg) => {
+            let url_term = Term::from_field_text(fields.url, &msg.url);
+            let lang = crate::lang_detect::detect(&msg.content);
+            let doc = doc!(
+                fields.content => msg.content,
+                fields.url => msg.url,
+                fields.chat_id => msg.chat_id,
+                fields.post_time => tantivy::DateTime::from_timestamp_secs(msg.post_time.timestamp()),
+                fields.sender => msg.sender,
+                fields.media_type => msg.media_type.as_str(),
+                fields.lang => lang.as_str(),
+            );
+            writer.delete_term(url_term);
+            if let Err(e) = writer.add_document(doc) {
+                tracing::error!("Failed to stage indexed document: {}", e);
+            }
+        }
+        WriterOp::Delete(url) => {
+            writer.delete_term(Term::from_field_text(fields.url, &url));
+        }
+        WriterOp::DeleteChat(chat_id) => {
+            writer.delete_term(Term::from_field_i64(fields.chat_id, chat_id));
+        }
+        WriterOp::Flush(_) => unreachable!("Flush is drained by run_writer_actor before reaching apply_write"),
+        WriterOp::Optimize(_) => {
+            unreachable!("Optimize is drained by run_writer_actor before reaching apply_write")
+        }
+        WriterOp::RebuildChat(..) => {
+            unreachable!("RebuildChat is drained by run_writer_actor before reaching apply_write")
+        }
+    }
+}
+
+/// Background writer actor: drains `rx`, staging each op on `writer`
+/// without committing, and only commits (then reloads `reader`) once
+/// either `WRITER_BATCH_SIZE` ops have accumulated or `WRITER_DEBOUNCE`
+/// has passed since the batch's first op — turning many small per-message
+/// commits (each an fsync) into one. A `Flush` op always commits
+/// immediately and replies once the reader has reloaded, so callers that
+/// need read-your-writes aren't subject to the debounce; an `Optimize` op
+/// does the same plus a full segment merge and garbage collection (see
+/// [`merge_segments`]); a `RebuildChat` op does the same plus a
+/// delete-and-reinsert of one chat's documents (see [`rebuild_chat`]).
+async fn run_writer_actor(
+    mut writer: IndexWriter,
+    reader: IndexReader,
+    index: Index,
+    fields: IndexFields,
+    mut rx: mpsc::Receiver<WriterOp>,
+) {
+    while let Some(op) = rx.recv().await {
+        let mut dirty = match op {
+            WriterOp::Flush(reply) => {
+                let _ = reply.send(commit_and_reload(&mut writer, &reader));
+                false
+            }
+            WriterOp::Optimize(reply) => {
+                let _ = reply.send(merge_segments(&mut writer, &index, &reader).await);
+                false
+            }
+            WriterOp::RebuildChat(chat_id, messages, reply) => {
+                let _ = reply.send(rebuild_chat(&mut writer, &reader, &fields, chat_id, messages));
+                false
+            }
+            op => {
+                apply_write(&mut writer, &fields, op);
+                true
+            }
+        };
+
+        let mut batched = 1;
+        let deadline = tokio::time::sleep(WRITER_DEBOUNCE);
+        tokio::pin!(deadline);
+        while batched < WRITER_BATCH_SIZE {
+            tokio::select! {
+                maybe_op = rx.recv() => {
+                    match maybe_op {
+                        Some(WriterOp::Flush(reply)) => {
+                            let _ = reply.send(commit_and_reload(&mut writer, &reader));
+                            dirty = false;
+                        }
+                        Some(WriterOp::Optimize(reply)) => {
+                            let _ = reply.send(merge_segments(&mut writer, &index, &reader).await);
+                            dirty = false;
+                        }
+                        Some(WriterOp::RebuildChat(chat_id, messages, reply)) => {
+                            let _ = reply.send(rebuild_chat(&mut writer, &reader, &fields, chat_id, messages));
+                            dirty = false;
+                        }
+                        Some(op) => {
+                            apply_write(&mut writer, &fields, op);
+                            dirty = true;
+                            batched += 1;
+                        }
+                        None => break,
+                    }
+                }
+                _ = &mut deadline => break,
+            }
+        }
+
+        if dirty && let Err(e) = commit_and_reload(&mut writer, &reader) {
+            tracing::error!("Failed to commit batched index writes: {}", e);
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -603,12 +1412,14 @@ mod tests {
             chat_id: 123,
             post_time: Utc::now(),
             sender: "Alice".to_string(),
+            media_type: MediaType::Text,
         };
 
         indexer.add_document(msg.clone()).await.unwrap();
+        indexer.flush().await.unwrap();
 
         // Search for it
-        let results = indexer.search("test", None, 10, 1).await.unwrap();
+        let results = indexer.search("test", None, 10, 1, MatchMode::Exact).await.unwrap();
         assert_eq!(results.total_results, 1);
         assert_eq!(results.hits[0].msg.content, msg.content);
     }
@@ -624,17 +1435,23 @@ mod tests {
             chat_id: 123,
             post_time: Utc::now(),
             sender: "Bob".to_string(),
+            media_type: MediaType::Text,
         };
 
-        indexer.add_document(msg).await.unwrap();
+        indexer.add_document(msg.clone()).await.unwrap();
+        indexer.flush().await.unwrap();
 
         // Update
         indexer
-            .update_document("https://t.me/c/123/456", "updated content")
+            .update_document(IndexMsg {
+                content: "updated content".to_string(),
+                ..msg
+            })
             .await
             .unwrap();
+        indexer.flush().await.unwrap();
 
-        let results = indexer.search("updated", None, 10, 1).await.unwrap();
+        let results = indexer.search("updated", None, 10, 1, MatchMode::Exact).await.unwrap();
         assert_eq!(results.total_results, 1);
 
         // Delete
@@ -642,7 +1459,8 @@ mod tests {
             .delete_document("https://t.me/c/123/456")
             .await
             .unwrap();
-        let results = indexer.search("updated", None, 10, 1).await.unwrap();
+        indexer.flush().await.unwrap();
+        let results = indexer.search("updated", None, 10, 1, MatchMode::Exact).await.unwrap();
         assert_eq!(results.total_results, 0);
     }
 
@@ -660,6 +1478,7 @@ mod tests {
                 chat_id: 123,
                 post_time: Utc::now(),
                 sender: "User".to_string(),
+                media_type: MediaType::Text,
             })
             .await
             .unwrap();
@@ -671,14 +1490,16 @@ mod tests {
                 chat_id: 123,
                 post_time: Utc::now(),
                 sender: "User".to_string(),
+                media_type: MediaType::Text,
             })
             .await
             .unwrap();
+        indexer.flush().await.unwrap();
 
-        let results = indexer.search("*", None, 10, 1).await.unwrap();
+        let results = indexer.search("*", None, 10, 1, MatchMode::Exact).await.unwrap();
         assert_eq!(results.total_results, 1);
 
-        let results = indexer.search("second", None, 10, 1).await.unwrap();
+        let results = indexer.search("second", None, 10, 1, MatchMode::Exact).await.unwrap();
         assert_eq!(results.total_results, 1);
         assert_eq!(results.hits[0].msg.url, url);
     }
@@ -696,13 +1517,15 @@ mod tests {
                 chat_id,
                 post_time: Utc::now(),
                 sender: "User".to_string(),
+                media_type: MediaType::Text,
             };
             indexer.add_document(msg).await.unwrap();
         }
+        indexer.flush().await.unwrap();
 
         // Search in specific chats
         let results = indexer
-            .search("message", Some(&[100, 200]), 10, 1)
+            .search("message", Some(&[100, 200]), 10, 1, MatchMode::Exact)
             .await
             .unwrap();
         assert_eq!(results.total_results, 2);
@@ -720,9 +1543,11 @@ mod tests {
                 chat_id,
                 post_time: Utc::now(),
                 sender: "User".to_string(),
+                media_type: MediaType::Text,
             };
             indexer.add_document(msg).await.unwrap();
         }
+        indexer.flush().await.unwrap();
 
         let mut chats = indexer.list_indexed_chats().await.unwrap();
         chats.sort();
@@ -741,15 +1566,55 @@ mod tests {
             chat_id: 123,
             post_time: Utc::now(),
             sender: "User".to_string(),
+            media_type: MediaType::Text,
         };
         indexer.add_document(msg).await.unwrap();
+        indexer.flush().await.unwrap();
 
         // Search for single character that appears multiple times
-        let results = indexer.search("人", None, 10, 1).await.unwrap();
+        let results = indexer.search("人", None, 10, 1, MatchMode::Exact).await.unwrap();
         assert_eq!(results.total_results, 1);
         assert!(results.hits[0].highlighted.contains("<b>人</b>"));
     }
 
+    #[tokio::test]
+    async fn test_chinese_word_segmentation_matches_whole_words() {
+        let temp_dir = TempDir::new().unwrap();
+        let indexer = Indexer::new(temp_dir.path(), true).await.unwrap();
+
+        indexer
+            .add_document(IndexMsg {
+                content: "人人都在说这个人很好".to_string(),
+                url: "https://t.me/c/123/1".to_string(),
+                chat_id: 123,
+                post_time: Utc::now(),
+                sender: "User".to_string(),
+                media_type: MediaType::Text,
+            })
+            .await
+            .unwrap();
+        indexer.flush().await.unwrap();
+
+        // Segmentation produces "人人" and "很好" as whole-word tokens, so
+        // searching for them finds the document via dictionary words
+        // rather than incidental character overlap.
+        assert_eq!(
+            indexer.search("人人", None, 10, 1, MatchMode::Exact).await.unwrap().total_results,
+            1
+        );
+        assert_eq!(
+            indexer.search("很好", None, 10, 1, MatchMode::Exact).await.unwrap().total_results,
+            1
+        );
+        // A two-character query that never occurs as a contiguous
+        // dictionary word in the content is not found, proving matches are
+        // word-based rather than character n-grams.
+        assert_eq!(
+            indexer.search("好人", None, 10, 1, MatchMode::Exact).await.unwrap().total_results,
+            0
+        );
+    }
+
     #[tokio::test]
     async fn test_delete_chat_documents() {
         let temp_dir = TempDir::new().unwrap();
@@ -764,37 +1629,506 @@ mod tests {
                     chat_id,
                     post_time: Utc::now(),
                     sender: "User".to_string(),
+                    media_type: MediaType::Text,
                 };
                 indexer.add_document(msg).await.unwrap();
             }
         }
+        indexer.flush().await.unwrap();
 
         // Verify all messages are indexed
-        let results = indexer.search("message", None, 100, 1).await.unwrap();
+        let results = indexer.search("message", None, 100, 1, MatchMode::Exact).await.unwrap();
         assert_eq!(results.total_results, 15); // 3 chats * 5 messages
 
         // Delete all documents from chat 200
         indexer.delete_chat_documents(200).await.unwrap();
+        indexer.flush().await.unwrap();
 
         // Verify chat 200 messages are gone
         let results = indexer
-            .search("message", Some(&[200]), 100, 1)
+            .search("message", Some(&[200]), 100, 1, MatchMode::Exact)
             .await
             .unwrap();
         assert_eq!(results.total_results, 0);
 
         // Verify other chats still exist
         let results = indexer
-            .search("message", Some(&[100, 300]), 100, 1)
+            .search("message", Some(&[100, 300]), 100, 1, MatchMode::Exact)
             .await
             .unwrap();
         assert_eq!(results.total_results, 10); // 2 chats * 5 messages
 
         // Delete all documents from chat 100
         indexer.delete_chat_documents(100).await.unwrap();
+        indexer.flush().await.unwrap();
 
         // Verify only chat 300 remains
-        let results = indexer.search("message", None, 100, 1).await.unwrap();
+        let results = indexer.search("message", None, 100, 1, MatchMode::Exact).await.unwrap();
         assert_eq!(results.total_results, 5);
     }
+
+    #[tokio::test]
+    async fn test_mixed_language_messages_are_searchable_and_tagged() {
+        let temp_dir = TempDir::new().unwrap();
+        let indexer = Indexer::new(temp_dir.path(), true).await.unwrap();
+
+        indexer
+            .add_document(IndexMsg {
+                content: "这是一条测试消息".to_string(),
+                url: "https://t.me/c/1/1".to_string(),
+                chat_id: 1,
+                post_time: Utc::now(),
+                sender: "User".to_string(),
+                media_type: MediaType::Text,
+            })
+            .await
+            .unwrap();
+
+        indexer
+            .add_document(IndexMsg {
+                content: "これはテストメッセージです".to_string(),
+                url: "https://t.me/c/1/2".to_string(),
+                chat_id: 1,
+                post_time: Utc::now(),
+                sender: "User".to_string(),
+                media_type: MediaType::Text,
+            })
+            .await
+            .unwrap();
+
+        indexer
+            .add_document(IndexMsg {
+                content: "running tests for the indexer".to_string(),
+                url: "https://t.me/c/1/3".to_string(),
+                chat_id: 1,
+                post_time: Utc::now(),
+                sender: "User".to_string(),
+                media_type: MediaType::Text,
+            })
+            .await
+            .unwrap();
+        indexer.flush().await.unwrap();
+
+        // Each language's segmenter finds its own document. The Japanese
+        // segmenter keeps the katakana run テストメッセージ as one token.
+        assert_eq!(indexer.search("测试", None, 10, 1, MatchMode::Exact).await.unwrap().total_results, 1);
+        assert_eq!(indexer.search("テストメッセージ", None, 10, 1, MatchMode::Exact).await.unwrap().total_results, 1);
+        // The stemmer should match "running" against the stem "run".
+        assert_eq!(indexer.search("run", None, 10, 1, MatchMode::Exact).await.unwrap().total_results, 1);
+    }
+
+    #[tokio::test]
+    async fn test_fuzzy_search_tolerates_typos_and_suggests_correction() {
+        let temp_dir = TempDir::new().unwrap();
+        let indexer = Indexer::new(temp_dir.path(), true).await.unwrap();
+
+        indexer
+            .add_document(IndexMsg {
+                content: "hello world".to_string(),
+                url: "https://t.me/c/1/1".to_string(),
+                chat_id: 1,
+                post_time: Utc::now(),
+                sender: "User".to_string(),
+                media_type: MediaType::Text,
+            })
+            .await
+            .unwrap();
+        indexer.flush().await.unwrap();
+
+        // An exact search for the typo finds nothing.
+        let exact = indexer
+            .search("helo", None, 10, 1, MatchMode::Exact)
+            .await
+            .unwrap();
+        assert_eq!(exact.total_results, 0);
+        assert!(exact.suggestion.is_none());
+
+        // A fuzzy search recovers the document and suggests the fix.
+        let fuzzy = indexer
+            .search("helo", None, 10, 1, MatchMode::Fuzzy)
+            .await
+            .unwrap();
+        assert_eq!(fuzzy.total_results, 1);
+        assert_eq!(fuzzy.suggestion.as_deref(), Some("hello"));
+    }
+
+    #[tokio::test]
+    async fn test_optimize_merges_segments_and_keeps_documents_searchable() {
+        let temp_dir = TempDir::new().unwrap();
+        let indexer = Indexer::new(temp_dir.path(), true).await.unwrap();
+
+        // Each flush commits separately, so this leaves multiple segments.
+        for i in 1..=5 {
+            indexer
+                .add_document(IndexMsg {
+                    content: format!("message {}", i),
+                    url: format!("https://t.me/c/1/{}", i),
+                    chat_id: 1,
+                    post_time: Utc::now(),
+                    sender: "User".to_string(),
+                    media_type: MediaType::Text,
+                })
+                .await
+                .unwrap();
+            indexer.flush().await.unwrap();
+        }
+        assert!(indexer.index.searchable_segment_ids().unwrap().len() > 1);
+
+        indexer.optimize().await.unwrap();
+        assert_eq!(indexer.index.searchable_segment_ids().unwrap().len(), 1);
+
+        let results = indexer.search("message", None, 10, 1, MatchMode::Exact).await.unwrap();
+        assert_eq!(results.total_results, 5);
+    }
+
+    #[tokio::test]
+    async fn test_structured_query_field_filters_and_boolean_operators() {
+        let temp_dir = TempDir::new().unwrap();
+        let indexer = Indexer::new(temp_dir.path(), true).await.unwrap();
+
+        indexer
+            .add_document(IndexMsg {
+                content: "the quarterly report is ready".to_string(),
+                url: "https://t.me/c/1/1".to_string(),
+                chat_id: 1,
+                post_time: Utc::now(),
+                sender: "Alice".to_string(),
+                media_type: MediaType::Text,
+            })
+            .await
+            .unwrap();
+        indexer
+            .add_document(IndexMsg {
+                content: "the quarterly budget is delayed".to_string(),
+                url: "https://t.me/c/1/2".to_string(),
+                chat_id: 1,
+                post_time: Utc::now(),
+                sender: "Bob".to_string(),
+                media_type: MediaType::Text,
+            })
+            .await
+            .unwrap();
+        indexer.flush().await.unwrap();
+
+        // Unqualified terms search both content and sender by default.
+        let by_sender = indexer
+            .search("Alice", None, 10, 1, MatchMode::Exact)
+            .await
+            .unwrap();
+        assert_eq!(by_sender.total_results, 1);
+        assert_eq!(by_sender.hits[0].msg.sender, "Alice");
+
+        // A field-qualified term restricts the match to that field.
+        let qualified = indexer
+            .search("sender:Bob", None, 10, 1, MatchMode::Exact)
+            .await
+            .unwrap();
+        assert_eq!(qualified.total_results, 1);
+        assert_eq!(qualified.hits[0].msg.sender, "Bob");
+
+        // An exact phrase only matches messages containing that phrase.
+        let phrase = indexer
+            .search("\"report is ready\"", None, 10, 1, MatchMode::Exact)
+            .await
+            .unwrap();
+        assert_eq!(phrase.total_results, 1);
+        assert!(phrase.hits[0].msg.content.contains("report is ready"));
+
+        // Negation excludes messages containing the negated term.
+        let negated = indexer
+            .search("quarterly -budget", None, 10, 1, MatchMode::Exact)
+            .await
+            .unwrap();
+        assert_eq!(negated.total_results, 1);
+        assert!(negated.hits[0].msg.content.contains("report"));
+
+        // Boolean AND/OR combine clauses.
+        let and_query = indexer
+            .search("quarterly AND budget", None, 10, 1, MatchMode::Exact)
+            .await
+            .unwrap();
+        assert_eq!(and_query.total_results, 1);
+
+        let or_query = indexer
+            .search("report OR budget", None, 10, 1, MatchMode::Exact)
+            .await
+            .unwrap();
+        assert_eq!(or_query.total_results, 2);
+    }
+
+    #[tokio::test]
+    async fn test_structured_query_date_range_and_sender_highlight() {
+        let temp_dir = TempDir::new().unwrap();
+        let indexer = Indexer::new(temp_dir.path(), true).await.unwrap();
+
+        let in_range = chrono::DateTime::parse_from_rfc3339("2024-03-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let out_of_range = chrono::DateTime::parse_from_rfc3339("2025-03-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+
+        indexer
+            .add_document(IndexMsg {
+                content: "message within range".to_string(),
+                url: "https://t.me/c/1/1".to_string(),
+                chat_id: 1,
+                post_time: in_range,
+                sender: "Alice".to_string(),
+                media_type: MediaType::Text,
+            })
+            .await
+            .unwrap();
+        indexer
+            .add_document(IndexMsg {
+                content: "message out of range".to_string(),
+                url: "https://t.me/c/1/2".to_string(),
+                chat_id: 1,
+                post_time: out_of_range,
+                sender: "Alice".to_string(),
+                media_type: MediaType::Text,
+            })
+            .await
+            .unwrap();
+        indexer.flush().await.unwrap();
+
+        let ranged = indexer
+            .search(
+                "post_time:[2024-01-01T00:00:00Z TO 2024-06-30T00:00:00Z]",
+                None,
+                10,
+                1,
+                MatchMode::Exact,
+            )
+            .await
+            .unwrap();
+        assert_eq!(ranged.total_results, 1);
+        assert!(ranged.hits[0].msg.content.contains("within range"));
+
+        // Bare YYYY-MM-DD bounds (Tantivy's own grammar requires RFC3339)
+        // are widened by `normalize_bare_date_ranges` before parsing.
+        let bare_date_ranged = indexer
+            .search(
+                "post_time:[2024-01-01 TO 2024-06-30]",
+                None,
+                10,
+                1,
+                MatchMode::Exact,
+            )
+            .await
+            .unwrap();
+        assert_eq!(bare_date_ranged.total_results, 1);
+        assert!(
+            bare_date_ranged.hits[0]
+                .msg
+                .content
+                .contains("within range")
+        );
+
+        // A query that only matches the sender field is highlighted there.
+        let sender_match = indexer
+            .search("sender:Alice", None, 10, 1, MatchMode::Exact)
+            .await
+            .unwrap();
+        assert_eq!(sender_match.total_results, 2);
+        for hit in &sender_match.hits {
+            assert!(hit.highlighted.contains("<b>Alice</b>"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_ranks_by_relevance_with_score_and_ellipses() {
+        let temp_dir = TempDir::new().unwrap();
+        let indexer = Indexer::new(temp_dir.path(), true).await.unwrap();
+
+        // One document mentions "rust" once, buried in a long message; the
+        // other repeats it, so it should score higher and rank first even
+        // though it was indexed second.
+        indexer
+            .add_document(IndexMsg {
+                content: "a very long message about many unrelated topics that only mentions rust a single time near the very end of all this padding text".to_string(),
+                url: "https://t.me/c/1/1".to_string(),
+                chat_id: 1,
+                post_time: Utc::now(),
+                sender: "User".to_string(),
+                media_type: MediaType::Text,
+            })
+            .await
+            .unwrap();
+        indexer
+            .add_document(IndexMsg {
+                content: "rust rust rust".to_string(),
+                url: "https://t.me/c/1/2".to_string(),
+                chat_id: 1,
+                post_time: Utc::now(),
+                sender: "User".to_string(),
+                media_type: MediaType::Text,
+            })
+            .await
+            .unwrap();
+        indexer.flush().await.unwrap();
+
+        let results = indexer
+            .search("rust", None, 10, 1, MatchMode::Exact)
+            .await
+            .unwrap();
+        assert_eq!(results.total_results, 2);
+        assert_eq!(results.hits[0].msg.url, "https://t.me/c/1/2");
+        assert!(results.hits[0].score >= results.hits[1].score);
+
+        // A preview out of the middle of a long message is marked with an
+        // ellipsis on the truncated side(s).
+        assert!(results.hits[1].highlighted.contains('…'));
+    }
+
+    #[tokio::test]
+    async fn test_search_with_correction_prefers_common_term_and_reruns() {
+        let temp_dir = TempDir::new().unwrap();
+        let indexer = Indexer::new(temp_dir.path(), true).await.unwrap();
+
+        // "hello" (one edit from the typo "helo") appears in three
+        // documents and "hullo" (two edits away) in only one, so the
+        // correction should pick "hello".
+        for i in 1..=3 {
+            indexer
+                .add_document(IndexMsg {
+                    content: "hello world".to_string(),
+                    url: format!("https://t.me/c/1/{}", i),
+                    chat_id: 1,
+                    post_time: Utc::now(),
+                    sender: "User".to_string(),
+                    media_type: MediaType::Text,
+                })
+                .await
+                .unwrap();
+        }
+        indexer
+            .add_document(IndexMsg {
+                content: "hullo there".to_string(),
+                url: "https://t.me/c/1/4".to_string(),
+                chat_id: 1,
+                post_time: Utc::now(),
+                sender: "User".to_string(),
+                media_type: MediaType::Text,
+            })
+            .await
+            .unwrap();
+        indexer.flush().await.unwrap();
+
+        let corrected = indexer
+            .search_with_correction("helo", None, 10, 1)
+            .await
+            .unwrap();
+        assert_eq!(corrected.original_total_results, 0);
+        assert_eq!(corrected.suggestion.as_deref(), Some("hello"));
+        assert_eq!(corrected.result.total_results, 3);
+
+        // A query with enough hits already is returned without a suggestion.
+        let uncorrected = indexer
+            .search_with_correction("hello", None, 10, 1)
+            .await
+            .unwrap();
+        assert_eq!(uncorrected.original_total_results, 3);
+        assert!(uncorrected.suggestion.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_update_document_is_atomic_upsert_without_intervening_flush() {
+        let temp_dir = TempDir::new().unwrap();
+        let indexer = Indexer::new(temp_dir.path(), true).await.unwrap();
+
+        let msg = IndexMsg {
+            content: "original content".to_string(),
+            url: "https://t.me/c/123/456".to_string(),
+            chat_id: 123,
+            post_time: Utc::now(),
+            sender: "Bob".to_string(),
+            media_type: MediaType::Text,
+        };
+        indexer.add_document(msg.clone()).await.unwrap();
+
+        // The update is enqueued right behind the add, with no flush in
+        // between: both land in the same writer transaction, so the
+        // eventual commit must see exactly one document for this URL,
+        // carrying the updated content. This doesn't depend on the old
+        // document being visible to a searcher yet, unlike a
+        // read-then-write update would.
+        indexer
+            .update_document(IndexMsg {
+                content: "updated content".to_string(),
+                ..msg
+            })
+            .await
+            .unwrap();
+        indexer.flush().await.unwrap();
+
+        let results = indexer.search("*", None, 10, 1, MatchMode::Exact).await.unwrap();
+        assert_eq!(results.total_results, 1);
+        assert_eq!(results.hits[0].msg.content, "updated content");
+    }
+
+    #[tokio::test]
+    async fn test_unlock_removes_lock_and_allows_reopen() {
+        let temp_dir = TempDir::new().unwrap();
+        {
+            let indexer = Indexer::new(temp_dir.path(), true).await.unwrap();
+            indexer.flush().await.unwrap();
+        }
+
+        // Whether or not the lock file is still present after the writer
+        // was dropped, unlock() must succeed either way.
+        Indexer::unlock(temp_dir.path()).await.unwrap();
+
+        // And the directory must still be usable as an index afterwards.
+        let indexer = Indexer::new(temp_dir.path(), false).await.unwrap();
+        let results = indexer.search("*", None, 10, 1, MatchMode::Exact).await.unwrap();
+        assert_eq!(results.total_results, 0);
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_chat_replaces_only_target_chat() {
+        let temp_dir = TempDir::new().unwrap();
+        let indexer = Indexer::new(temp_dir.path(), true).await.unwrap();
+
+        let old_msg = IndexMsg {
+            content: "stale message".to_string(),
+            url: "https://t.me/c/123/1".to_string(),
+            chat_id: 123,
+            post_time: Utc::now(),
+            sender: "Alice".to_string(),
+            media_type: MediaType::Text,
+        };
+        let other_chat_msg = IndexMsg {
+            content: "untouched message".to_string(),
+            url: "https://t.me/c/456/1".to_string(),
+            chat_id: 456,
+            post_time: Utc::now(),
+            sender: "Carol".to_string(),
+            media_type: MediaType::Text,
+        };
+        indexer.add_document(old_msg).await.unwrap();
+        indexer.add_document(other_chat_msg).await.unwrap();
+        indexer.flush().await.unwrap();
+
+        let fresh_msg = IndexMsg {
+            content: "rebuilt message".to_string(),
+            url: "https://t.me/c/123/2".to_string(),
+            chat_id: 123,
+            post_time: Utc::now(),
+            sender: "Bob".to_string(),
+            media_type: MediaType::Text,
+        };
+        indexer.rebuild_chat(123, vec![fresh_msg]).await.unwrap();
+
+        let stale = indexer.search("stale", None, 10, 1, MatchMode::Exact).await.unwrap();
+        assert_eq!(stale.total_results, 0);
+
+        let rebuilt = indexer.search("rebuilt", None, 10, 1, MatchMode::Exact).await.unwrap();
+        assert_eq!(rebuilt.total_results, 1);
+        assert_eq!(rebuilt.hits[0].msg.chat_id, 123);
+
+        let untouched = indexer.search("untouched", None, 10, 1, MatchMode::Exact).await.unwrap();
+        assert_eq!(untouched.total_results, 1);
+        assert_eq!(untouched.hits[0].msg.chat_id, 456);
+    }
 }
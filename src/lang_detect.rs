@@ -0,0 +1,91 @@
+//! Lightweight script-based language classifier used to route message
+//! content to the right Tantivy tokenizer (see
+//! `crate::indexer::MultiLangTokenizer`).
+//!
+//! This is deliberately not a statistical language-id model: Telegram
+//! messages are short and mixed-script groups are common, so a per-codepoint
+//! Unicode script tally is enough to pick the right segmentation strategy
+//! without pulling in a heavy detection crate.
+
+/// A coarse language classification, granular enough to pick a tokenizer.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    Chinese,
+    Japanese,
+    Korean,
+    /// Everything else, including Latin-script text.
+    Other,
+}
+
+impl Lang {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Lang::Chinese => "zh",
+            Lang::Japanese => "ja",
+            Lang::Korean => "ko",
+            Lang::Other => "other",
+        }
+    }
+}
+
+/// Classify `text` by the first CJK-relevant script it contains: kana means
+/// Japanese (Chinese text has no kana), hangul means Korean, and Han
+/// ideographs with neither of those present default to Chinese, since jieba
+/// already segments that case well. Text with no CJK codepoints at all is
+/// `Lang::Other`.
+pub fn detect(text: &str) -> Lang {
+    let mut has_han = false;
+    let mut has_kana = false;
+    let mut has_hangul = false;
+
+    for c in text.chars() {
+        let cp = c as u32;
+        if (0x3040..=0x30FF).contains(&cp) {
+            has_kana = true;
+        } else if (0xAC00..=0xD7A3).contains(&cp) || (0x1100..=0x11FF).contains(&cp) {
+            has_hangul = true;
+        } else if (0x4E00..=0x9FFF).contains(&cp) || (0x3400..=0x4DBF).contains(&cp) {
+            has_han = true;
+        }
+    }
+
+    if has_kana {
+        Lang::Japanese
+    } else if has_hangul {
+        Lang::Korean
+    } else if has_han {
+        Lang::Chinese
+    } else {
+        Lang::Other
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_chinese() {
+        assert_eq!(detect("这是一条测试消息"), Lang::Chinese);
+    }
+
+    #[test]
+    fn test_detects_japanese_via_kana() {
+        assert_eq!(detect("これはテストメッセージです"), Lang::Japanese);
+    }
+
+    #[test]
+    fn test_detects_korean() {
+        assert_eq!(detect("이것은 테스트 메시지입니다"), Lang::Korean);
+    }
+
+    #[test]
+    fn test_defaults_to_other_for_latin_text() {
+        assert_eq!(detect("this is a test message"), Lang::Other);
+    }
+
+    #[test]
+    fn test_empty_text_is_other() {
+        assert_eq!(detect(""), Lang::Other);
+    }
+}
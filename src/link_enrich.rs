@@ -0,0 +1,274 @@
+//! Optional link enrichment: fetch a linked page's `<title>` and meta
+//! description and append them to a message's indexed content, so a message
+//! that's mostly a bare URL becomes findable by the destination page's
+//! words.
+//!
+//! The URL extraction and embedded cache below always compile; actually
+//! fetching pages requires the `link-enrich` Cargo feature, mirroring how
+//! `crate::sinks::AmqpSink` is gated behind `amqp-sink`. `BackendBot::new` rejects `enrich_links: true` up front with
+//! a clear config error if the feature isn't compiled in, rather than
+//! silently doing nothing once a message with a link comes in.
+
+use crate::types::{Error, Result};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Bound on how many links per message are enriched, so one message can't
+/// trigger an unbounded number of outbound fetches.
+pub const MAX_LINKS_PER_MESSAGE: usize = 3;
+
+/// Extract up to [`MAX_LINKS_PER_MESSAGE`] distinct HTTP(S) URLs from
+/// `text`, in the order they appear.
+pub fn extract_urls(text: &str) -> Vec<String> {
+    static URL_RE: std::sync::LazyLock<regex::Regex> =
+        std::sync::LazyLock::new(|| regex::Regex::new(r#"https?://[^\s<>"']+"#).unwrap());
+
+    let mut seen = std::collections::HashSet::new();
+    let mut urls = Vec::new();
+    for m in URL_RE.find_iter(text) {
+        let url = m.as_str().trim_end_matches(['.', ',', '!', '?', ')', ']', '}']);
+        if seen.insert(url.to_string()) {
+            urls.push(url.to_string());
+            if urls.len() >= MAX_LINKS_PER_MESSAGE {
+                break;
+            }
+        }
+    }
+    urls
+}
+
+/// Cached outcome of fetching a single URL: `None` means the URL was
+/// fetched but nothing usable came of it (non-HTML, fetch error, no
+/// title/description), so it isn't retried on every message that links it.
+pub type LinkInfo = Option<String>;
+
+/// Backend-agnostic store caching `url -> enrichment text`, shared across
+/// chats so the same link is only ever fetched once.
+#[async_trait]
+pub trait LinkCacheStore: Send + Sync {
+    /// `Ok(None)` means the URL has never been fetched; `Ok(Some(info))`
+    /// means it has (`info` itself may still be `None`, see [`LinkInfo`]).
+    async fn get(&self, url: &str) -> Result<Option<LinkInfo>>;
+
+    /// Record the outcome of fetching `url`.
+    async fn insert(&self, url: &str, info: LinkInfo) -> Result<()>;
+}
+
+/// In-memory store backed by a [`DashMap`].
+#[derive(Clone, Default)]
+pub struct InMemLinkCacheStore {
+    data: Arc<DashMap<String, LinkInfo>>,
+}
+
+impl InMemLinkCacheStore {
+    /// Create an empty in-memory store.
+    pub fn new() -> Self {
+        Self {
+            data: Arc::new(DashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl LinkCacheStore for InMemLinkCacheStore {
+    async fn get(&self, url: &str) -> Result<Option<LinkInfo>> {
+        Ok(self.data.get(url).map(|v| v.clone()))
+    }
+
+    async fn insert(&self, url: &str, info: LinkInfo) -> Result<()> {
+        self.data.insert(url.to_string(), info);
+        Ok(())
+    }
+}
+
+/// SQLite-backed cache in the database file given to [`Self::open`]
+/// (`main` uses the backend's `state.db` in its index directory).
+pub struct SqliteLinkCacheStore {
+    conn: tokio::sync::Mutex<rusqlite::Connection>,
+}
+
+impl SqliteLinkCacheStore {
+    /// Open (creating if necessary) the cache table in `db_path`.
+    pub fn open(db_path: &Path) -> Result<Self> {
+        let conn = rusqlite::Connection::open(db_path)
+            .map_err(|e| Error::Config(format!("Failed to open link cache db: {}", e)))?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS link_cache (
+                url        TEXT PRIMARY KEY,
+                enrichment TEXT
+            )",
+            [],
+        )
+        .map_err(|e| Error::Config(format!("Failed to create link_cache table: {}", e)))?;
+        Ok(Self {
+            conn: tokio::sync::Mutex::new(conn),
+        })
+    }
+}
+
+#[async_trait]
+impl LinkCacheStore for SqliteLinkCacheStore {
+    async fn get(&self, url: &str) -> Result<Option<LinkInfo>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn
+            .prepare("SELECT enrichment FROM link_cache WHERE url = ?1")
+            .map_err(|e| Error::Config(e.to_string()))?;
+        let row = stmt
+            .query_row([url], |row| row.get::<_, Option<String>>(0))
+            .ok();
+        Ok(row)
+    }
+
+    async fn insert(&self, url: &str, info: LinkInfo) -> Result<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            "INSERT INTO link_cache (url, enrichment) VALUES (?1, ?2)
+             ON CONFLICT(url) DO UPDATE SET enrichment = excluded.enrichment",
+            rusqlite::params![url, info],
+        )
+        .map_err(|e| Error::Config(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "link-enrich")]
+mod fetch {
+    use super::*;
+    use std::time::Duration;
+
+    /// Bounded-fetch limits, mirroring url-bot-rs: a hard cap on how much of
+    /// the response body is buffered (titles/descriptions are always in the
+    /// first few KB), a request timeout, and a small redirect cap, so a
+    /// misbehaving server can't stall or loop indexing.
+    const MAX_RESPONSE_BYTES: usize = 64 * 1024;
+    const FETCH_TIMEOUT: Duration = Duration::from_secs(5);
+    const MAX_REDIRECTS: usize = 5;
+
+    static TITLE_RE: std::sync::LazyLock<regex::Regex> =
+        std::sync::LazyLock::new(|| regex::Regex::new(r"(?is)<title[^>]*>(.*?)</title>").unwrap());
+    static DESCRIPTION_RE: std::sync::LazyLock<regex::Regex> = std::sync::LazyLock::new(|| {
+        regex::Regex::new(r#"(?is)<meta\s+[^>]*name=["']description["'][^>]*content=["']([^"']*)["'][^>]*/?>"#)
+            .unwrap()
+    });
+
+    /// Fetch `url` and extract its `<title>`/meta description, if it's HTML.
+    /// Returns `Ok(None)` (not an error) for anything that can't be
+    /// enriched: a non-2xx response, a non-HTML content type, or a page with
+    /// no title/description — only genuine client setup failures are `Err`.
+    pub async fn fetch_enrichment(url: &str, proxy_url: Option<&str>) -> Result<LinkInfo> {
+        let mut builder = reqwest::Client::builder()
+            .timeout(FETCH_TIMEOUT)
+            .redirect(reqwest::redirect::Policy::limited(MAX_REDIRECTS));
+        if let Some(proxy_url) = proxy_url {
+            let proxy = reqwest::Proxy::all(proxy_url)
+                .map_err(|e| Error::Config(format!("Invalid link enrichment proxy: {}", e)))?;
+            builder = builder.proxy(proxy);
+        }
+        let client = builder
+            .build()
+            .map_err(|e| Error::Other(anyhow::anyhow!("Failed to build enrichment client: {}", e)))?;
+
+        let response = match client.get(url).send().await {
+            Ok(response) => response,
+            Err(_) => return Ok(None),
+        };
+        if !response.status().is_success() {
+            return Ok(None);
+        }
+        let is_html = response
+            .headers()
+            .get(reqwest::header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok())
+            .is_some_and(|ct| ct.starts_with("text/html"));
+        if !is_html {
+            return Ok(None);
+        }
+
+        let mut body = Vec::new();
+        let mut response = response;
+        while body.len() < MAX_RESPONSE_BYTES {
+            let chunk = match response.chunk().await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(_) => break,
+            };
+            let remaining = MAX_RESPONSE_BYTES - body.len();
+            if chunk.len() > remaining {
+                body.extend_from_slice(&chunk[..remaining]);
+                break;
+            }
+            body.extend_from_slice(&chunk);
+        }
+
+        let html = String::from_utf8_lossy(&body);
+        Ok(extract_title_and_description(&html))
+    }
+
+    fn extract_title_and_description(html: &str) -> LinkInfo {
+        let title = TITLE_RE
+            .captures(html)
+            .and_then(|c| c.get(1))
+            .map(|m| html_escape::decode_html_entities(m.as_str().trim()).into_owned())
+            .filter(|s| !s.is_empty());
+        let description = DESCRIPTION_RE
+            .captures(html)
+            .and_then(|c| c.get(1))
+            .map(|m| html_escape::decode_html_entities(m.as_str().trim()).into_owned())
+            .filter(|s| !s.is_empty());
+
+        match (title, description) {
+            (None, None) => None,
+            (Some(t), None) => Some(t),
+            (None, Some(d)) => Some(d),
+            (Some(t), Some(d)) => Some(format!("{} — {}", t, d)),
+        }
+    }
+}
+
+#[cfg(feature = "link-enrich")]
+pub use fetch::fetch_enrichment;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_urls() {
+        let text = "check this out https://example.com/page, and also (http://foo.bar/x?y=1)!";
+        assert_eq!(
+            extract_urls(text),
+            vec!["https://example.com/page", "http://foo.bar/x?y=1"]
+        );
+    }
+
+    #[test]
+    fn test_extract_urls_dedup_and_cap() {
+        let text = "https://a.com https://a.com https://b.com https://c.com https://d.com";
+        assert_eq!(extract_urls(text).len(), MAX_LINKS_PER_MESSAGE);
+    }
+
+    #[tokio::test]
+    async fn test_in_mem_link_cache_store() {
+        let store = InMemLinkCacheStore::new();
+        assert!(store.get("https://example.com").await.unwrap().is_none());
+
+        store
+            .insert("https://example.com", Some("Example — a page".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(
+            store.get("https://example.com").await.unwrap(),
+            Some(Some("Example — a page".to_string()))
+        );
+
+        // A fetch that found nothing usable is still cached (as `Some(None)`)
+        // so the URL isn't retried.
+        store.insert("https://empty.example.com", None).await.unwrap();
+        assert_eq!(
+            store.get("https://empty.example.com").await.unwrap(),
+            Some(None)
+        );
+    }
+}
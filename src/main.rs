@@ -4,19 +4,37 @@
 //! and Chinese word segmentation support.
 
 mod backend;
+mod chat_config;
+mod chat_meta;
+mod commands;
 mod config;
+#[cfg(feature = "encrypted-index")]
+mod encrypted_dir;
 mod frontend;
 mod indexer;
+mod lang_detect;
+mod link_enrich;
+mod metrics;
+mod msg_chat_map;
+mod ratelimit;
 mod session;
+mod sinks;
 mod storage;
+mod supervisor;
 mod types;
+mod url_normalize;
 mod utils;
 
 use anyhow::Result;
 use clap::Parser;
 use std::path::PathBuf;
+use std::sync::Arc;
 use tracing::{error, info, warn};
 
+/// How long to wait for supervised tasks to stop on their own after a
+/// shutdown signal before aborting whatever's left.
+const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
+
 #[derive(Parser, Debug)]
 #[command(name = "tg-searcher")]
 #[command(about = "A server to provide Telegram message searching")]
@@ -59,6 +77,25 @@ async fn main() -> Result<()> {
         .await
         .map_err(|e| anyhow::anyhow!("Failed to create directories: {}", e))?;
 
+    // Shared shutdown signal, restart-state registry, and metrics registry
+    // for the supervisor (see `supervisor::supervise`), threaded into every
+    // backend/frontend.
+    let shutdown = Arc::new(tokio::sync::Notify::new());
+    let registry = supervisor::Registry::new();
+    let metrics = metrics::Metrics::new();
+
+    if let Some(addr) = &config.common.metrics_addr {
+        let addr: std::net::SocketAddr = addr
+            .parse()
+            .map_err(|e| anyhow::anyhow!("Invalid metrics_addr '{}': {}", addr, e))?;
+        let metrics_server = metrics.clone();
+        tokio::spawn(async move {
+            if let Err(e) = metrics_server.serve(addr).await {
+                error!("Metrics endpoint stopped: {}", e);
+            }
+        });
+    }
+
     // Initialize sessions
     let mut sessions = std::collections::HashMap::new();
     for session_config in &config.sessions {
@@ -67,22 +104,49 @@ async fn main() -> Result<()> {
             .session_dir()
             .join(format!("{}.session", session_config.name));
 
-        let session = session::ClientSession::new(
+        let proxy = session_config.effective_proxy(&config.common).map_err(|e| {
+            anyhow::anyhow!(
+                "Invalid proxy config for session '{}': {}",
+                session_config.name,
+                e
+            )
+        })?;
+
+        let mut session = session::ClientSession::new(
             &session_file,
             session_config.name.clone(),
             config.common.api_id,
             &config.common.api_hash,
-            config.common.parse_proxy(),
+            proxy,
         )
         .await
         .map_err(|e| {
             anyhow::anyhow!("Failed to create session '{}': {}", session_config.name, e)
         })?;
 
-        // Start the session (login)
-        session.start(&session_config.phone).await.map_err(|e| {
-            anyhow::anyhow!("Failed to start session '{}': {}", session_config.name, e)
+        // Persist the chat-metadata cache across restarts instead of the
+        // default in-memory store, in its own SQLite file alongside the
+        // session database.
+        let meta_db_path = config
+            .common
+            .session_dir()
+            .join(format!("{}.meta.db", session_config.name));
+        let meta_store = chat_meta::SqliteChatMetaStore::open(&meta_db_path).map_err(|e| {
+            anyhow::anyhow!(
+                "Failed to open chat meta store for session '{}': {}",
+                session_config.name,
+                e
+            )
         })?;
+        session.set_meta_store(Arc::new(meta_store));
+
+        // Start the session (login), prompting on the terminal for secrets
+        session
+            .start(&session_config.phone, &session::TerminalAuthProvider)
+            .await
+            .map_err(|e| {
+                anyhow::anyhow!("Failed to start session '{}': {}", session_config.name, e)
+            })?;
 
         // Populate access hashes by fetching all dialogs
         // This ensures backends can access channels without warnings
@@ -116,33 +180,96 @@ async fn main() -> Result<()> {
             })?
             .clone();
 
-        // Create indexer for this backend
+        // Create indexer for this backend. A process killed rather than
+        // shut down cleanly can leave a stale writer lock behind, which
+        // makes the first open fail; recover by clearing the lock and
+        // retrying once before giving up for good.
         let index_dir = config.common.index_dir().join(&backend_config.id);
         let indexer = std::sync::Arc::new(
-            indexer::Indexer::new(&index_dir, args.clear)
-                .await
-                .map_err(|e| {
-                    anyhow::anyhow!(
-                        "Failed to create indexer for '{}': {}",
-                        backend_config.id,
-                        e
-                    )
-                })?,
+            match indexer::Indexer::new(&index_dir, args.clear).await {
+                Ok(indexer) => indexer,
+                Err(e) => {
+                    warn!(
+                        "Failed to open indexer for '{}' ({}), clearing stale lock and retrying",
+                        backend_config.id, e
+                    );
+                    indexer::Indexer::unlock(&index_dir).await.map_err(|e| {
+                        anyhow::anyhow!(
+                            "Failed to clear stale lock for '{}': {}",
+                            backend_config.id,
+                            e
+                        )
+                    })?;
+                    indexer::Indexer::new(&index_dir, args.clear)
+                        .await
+                        .map_err(|e| {
+                            anyhow::anyhow!(
+                                "Failed to create indexer for '{}': {}",
+                                backend_config.id,
+                                e
+                            )
+                        })?
+                }
+            },
         );
 
         // Create backend
-        let backend =
-            backend::BackendBot::new(&backend_config.id, backend_config, session, indexer)
-                .await
-                .map_err(|e| {
-                    anyhow::anyhow!("Failed to create backend '{}': {}", backend_config.id, e)
-                })?;
+        let mut backend = backend::BackendBot::new(
+            &backend_config.id,
+            backend_config,
+            session,
+            indexer,
+            shutdown.clone(),
+            metrics.clone(),
+        )
+        .await
+        .map_err(|e| {
+            anyhow::anyhow!("Failed to create backend '{}': {}", backend_config.id, e)
+        })?;
+
+        // Persist per-chat config across restarts instead of the default
+        // in-memory store, alongside the backend's index.
+        let state_db_path = index_dir.join("state.db");
+        let chat_config_store =
+            chat_config::SqliteChatConfigStore::open(&state_db_path).map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to open chat config store for backend '{}': {}",
+                    backend_config.id,
+                    e
+                )
+            })?;
+        backend.set_chat_config_store(std::sync::Arc::new(chat_config_store));
+
+        // Persist the msg_id -> share_id lookup across restarts instead of
+        // the default in-memory store, sharing the backend's state db.
+        let msg_chat_map_store =
+            msg_chat_map::SqliteMsgChatMapStore::open(&state_db_path).map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to open msg_chat_map store for backend '{}': {}",
+                    backend_config.id,
+                    e
+                )
+            })?;
+        backend.set_msg_chat_map_store(std::sync::Arc::new(msg_chat_map_store));
+
+        // Persist the link-enrichment cache across restarts instead of the
+        // default in-memory store, sharing the backend's state db.
+        let link_cache_store =
+            link_enrich::SqliteLinkCacheStore::open(&state_db_path).map_err(|e| {
+                anyhow::anyhow!(
+                    "Failed to open link cache store for backend '{}': {}",
+                    backend_config.id,
+                    e
+                )
+            })?;
+        backend.set_link_cache_store(std::sync::Arc::new(link_cache_store));
 
         let backend_arc = std::sync::Arc::new(backend);
         backends.insert(backend_config.id.clone(), backend_arc.clone());
     }
 
     info!("Created {} backend(s)", backends.len());
+    let backends_for_reload = backends.clone();
 
     // Initialize and start all backends
     for backend in backends.values() {
@@ -150,14 +277,19 @@ async fn main() -> Result<()> {
             anyhow::anyhow!("Failed to initialize backend '{}': {}", backend.id(), e)
         })?;
 
-        // Spawn backend event loop
+        // Spawn a supervised backend event loop: restarts with backoff on
+        // error, stops for good once `shutdown` is observed.
         let backend_clone = backend.clone();
         let backend_id = backend.id().to_string();
-        backend_tasks.push(tokio::spawn(async move {
-            if let Err(e) = backend_clone.run().await {
-                error!("Backend '{}' event loop error: {}", backend_id, e);
-            }
-        }));
+        backend_tasks.push(tokio::spawn(supervisor::supervise(
+            backend_id,
+            registry.clone(),
+            shutdown.clone(),
+            move || {
+                let backend = backend_clone.clone();
+                async move { backend.run().await }
+            },
+        )));
     }
 
     info!("Started all backend event loops");
@@ -165,6 +297,10 @@ async fn main() -> Result<()> {
     // Initialize frontends with storage
     let mut frontend_tasks = Vec::new();
     let mut frontend_count = config.frontends.len();
+    // Live config handles, kept around so the reload subsystem can update a
+    // running frontend's hot-reloadable fields without reaching into the
+    // task that owns it.
+    let mut frontend_configs = std::collections::HashMap::new();
 
     for frontend_config in &config.frontends {
         // Get the backend for this frontend
@@ -179,9 +315,30 @@ async fn main() -> Result<()> {
             })?
             .clone();
 
-        // Create storage for this frontend (in-memory)
-        let storage: std::sync::Arc<dyn storage::Storage> =
-            std::sync::Arc::new(storage::InMemoryStorage::new());
+        // Create storage for this frontend per its configured backend
+        let storage: std::sync::Arc<dyn storage::Storage> = match &frontend_config.config.storage
+        {
+            config::StorageConfig::Memory => {
+                std::sync::Arc::new(storage::InMemoryStorage::new())
+            }
+            config::StorageConfig::Redis {
+                url,
+                key_prefix,
+                ttl_secs,
+            } => {
+                let redis_storage =
+                    storage::RedisStorage::connect(url, key_prefix.clone(), *ttl_secs)
+                        .await
+                        .map_err(|e| {
+                            anyhow::anyhow!(
+                                "Failed to connect frontend '{}' to Redis: {}",
+                                frontend_config.id,
+                                e
+                            )
+                        })?;
+                std::sync::Arc::new(redis_storage)
+            }
+        };
 
         // Create frontend
         let mut frontend = frontend::BotFrontend::new(
@@ -190,6 +347,8 @@ async fn main() -> Result<()> {
             backend,
             storage,
             &config.common,
+            shutdown.clone(),
+            metrics.clone(),
         )
         .await
         .map_err(|e| {
@@ -205,13 +364,17 @@ async fn main() -> Result<()> {
             )
         })?;
 
-        // Spawn frontend event loop
+        frontend_configs.insert(frontend_config.id.clone(), frontend.config_handle());
+
+        // Spawn a supervised frontend event loop: restarts with backoff on
+        // error, stops for good once `shutdown` is observed.
         let frontend_id = frontend_config.id.clone();
-        frontend_tasks.push(tokio::spawn(async move {
-            if let Err(e) = frontend.run().await {
-                error!("Frontend '{}' event loop error: {}", frontend_id, e);
-            }
-        }));
+        frontend_tasks.push(tokio::spawn(supervisor::supervise(
+            frontend_id,
+            registry.clone(),
+            shutdown.clone(),
+            move || frontend.run(),
+        )));
         frontend_count += 1;
     }
 
@@ -221,19 +384,149 @@ async fn main() -> Result<()> {
         return Err(anyhow::anyhow!("No frontends configured"));
     }
 
-    info!("Initialization complete. Press Ctrl+C to stop.");
+    info!(
+        "Initialization complete. Press Ctrl+C (or send SIGTERM) to stop, or send SIGHUP to reload the config."
+    );
+
+    let mut running_config = config;
+    let mut sighup = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup())
+        .map_err(|e| anyhow::anyhow!("Failed to register SIGHUP handler: {}", e))?;
+    let mut sigterm = tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+        .map_err(|e| anyhow::anyhow!("Failed to register SIGTERM handler: {}", e))?;
+
+    loop {
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => {
+                result?;
+                info!("Received Ctrl+C");
+                break;
+            }
+            _ = sigterm.recv() => {
+                info!("Received SIGTERM");
+                break;
+            }
+            _ = sighup.recv() => {
+                info!("Received SIGHUP, reloading config from {:?}", args.config);
+                match reload_config(&args.config, &running_config, &backends_for_reload, &frontend_configs).await {
+                    Ok(new_config) => running_config = new_config,
+                    Err(e) => error!("Config reload failed, keeping previous config: {}", e),
+                }
+            }
+        }
+    }
 
-    // Wait for Ctrl+C signal
-    tokio::signal::ctrl_c().await?;
+    info!("Shutting down: signaling all backends/frontends to stop...");
+    shutdown.notify_waiters();
 
-    info!("Shutting down...");
+    let all_tasks: Vec<_> = backend_tasks.into_iter().chain(frontend_tasks).collect();
+    let abort_handles: Vec<_> = all_tasks.iter().map(|t| t.abort_handle()).collect();
+    let wait_for_tasks = async {
+        for task in all_tasks {
+            let _ = task.await;
+        }
+    };
 
-    // Note: Background tasks will be automatically cancelled when main exits
-    // In a production system, you might want to handle graceful shutdown of tasks
+    if tokio::time::timeout(SHUTDOWN_TIMEOUT, wait_for_tasks)
+        .await
+        .is_ok()
+    {
+        info!("All backends/frontends stopped cleanly");
+    } else {
+        warn!(
+            "Shutdown timed out after {:?}; aborting remaining tasks",
+            SHUTDOWN_TIMEOUT
+        );
+        for handle in abort_handles {
+            handle.abort();
+        }
+    }
+
+    info!("Flushing indexes before exit...");
+    for backend in backends.values() {
+        if let Err(e) = backend.flush_index().await {
+            error!("Failed to flush index for backend '{}': {}", backend.id(), e);
+        }
+    }
 
     Ok(())
 }
 
+/// Reload the config file, diff it against the currently running config, and
+/// apply whatever can be changed live. Only `BackendBotConfig`/
+/// `BotFrontendConfig` fields on backends/frontends that still exist after
+/// the reload can be updated this way, and only the ones that
+/// `BackendBot::apply_config`/`BotFrontend::apply_config` swap in; adding
+/// or removing a session,
+/// backend, or frontend, and changing a frontend's `bot_token`, `admin_id`,
+/// or `storage` backend, still requires a restart -- logged here rather than
+/// silently dropped, so an operator can act on it.
+async fn reload_config(
+    path: &std::path::Path,
+    running: &config::Config,
+    backends: &std::collections::HashMap<String, std::sync::Arc<backend::BackendBot>>,
+    frontend_configs: &std::collections::HashMap<
+        String,
+        std::sync::Arc<std::sync::RwLock<config::BotFrontendConfig>>,
+    >,
+) -> Result<config::Config> {
+    let new_config = config::Config::from_file(path)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to load config: {}", e))?;
+
+    let delta = running.diff(&new_config);
+    if delta.is_empty() {
+        info!("Config reload: no changes detected");
+        return Ok(new_config);
+    }
+
+    for (id, kind) in &delta.sessions {
+        warn!(
+            "Session '{}' {:?} in reloaded config; sessions require a restart to apply",
+            id, kind
+        );
+    }
+
+    for (id, kind) in &delta.backends {
+        match kind {
+            config::ChangeKind::Modified => {
+                if let (Some(backend), Some(backend_config)) =
+                    (backends.get(id), new_config.backends.iter().find(|b| &b.id == id))
+                {
+                    backend.apply_config(&backend_config.config);
+                }
+            }
+            config::ChangeKind::Added | config::ChangeKind::Removed => {
+                warn!(
+                    "Backend '{}' {:?} in reloaded config; adding/removing backends requires a restart",
+                    id, kind
+                );
+            }
+        }
+    }
+
+    for (id, kind) in &delta.frontends {
+        match kind {
+            config::ChangeKind::Modified => {
+                if let (Some(handle), Some(frontend_config)) = (
+                    frontend_configs.get(id),
+                    new_config.frontends.iter().find(|f| &f.id == id),
+                ) {
+                    frontend::BotFrontend::apply_config(id, handle, &frontend_config.config);
+                }
+            }
+            config::ChangeKind::Added | config::ChangeKind::Removed => {
+                warn!(
+                    "Frontend '{}' {:?} in reloaded config; adding/removing frontends requires a restart",
+                    id, kind
+                );
+            }
+        }
+    }
+
+    info!("Config reload applied");
+    Ok(new_config)
+}
+
 fn init_logging(debug: bool) {
     use tracing_subscriber::{EnvFilter, fmt, prelude::*};
 
@@ -0,0 +1,212 @@
+//! Prometheus metrics endpoint
+//!
+//! No metrics crate is pulled in: counters/gauges are plain atomics keyed by
+//! component id, and the text-exposition format is simple enough to write by
+//! hand. [`Metrics`] is cheaply `Clone`able (it's just a couple of `Arc`s) so
+//! it can be handed to every backend and frontend the way `supervisor::Registry`
+//! is.
+
+use crate::indexer::Indexer;
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+/// Counters for a single backend or frontend, keyed by its configured id.
+#[derive(Default)]
+struct ComponentCounters {
+    messages_indexed: AtomicU64,
+    messages_edited: AtomicU64,
+    messages_deleted: AtomicU64,
+    event_loop_errors: AtomicU64,
+    search_queries: AtomicU64,
+    search_latency_ms_sum: AtomicU64,
+    pagination_cache_hits: AtomicU64,
+    pagination_cache_misses: AtomicU64,
+}
+
+/// Shared metrics registry, threaded into every backend/frontend alongside
+/// `shutdown` and `supervisor::Registry`.
+#[derive(Clone, Default)]
+pub struct Metrics {
+    components: Arc<Mutex<HashMap<String, Arc<ComponentCounters>>>>,
+    indexers: Arc<Mutex<HashMap<String, Arc<Indexer>>>>,
+}
+
+impl Metrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn component(&self, id: &str) -> Arc<ComponentCounters> {
+        self.components
+            .lock()
+            .unwrap()
+            .entry(id.to_string())
+            .or_default()
+            .clone()
+    }
+
+    /// Register a backend's indexer so `/metrics` can report its current
+    /// document count. Call once, from `BackendBot::new`.
+    pub fn register_indexer(&self, backend_id: &str, indexer: Arc<Indexer>) {
+        self.indexers
+            .lock()
+            .unwrap()
+            .insert(backend_id.to_string(), indexer);
+    }
+
+    pub fn record_indexed(&self, backend_id: &str) {
+        self.component(backend_id)
+            .messages_indexed
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_edited(&self, backend_id: &str) {
+        self.component(backend_id)
+            .messages_edited
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_deleted(&self, backend_id: &str, count: u64) {
+        self.component(backend_id)
+            .messages_deleted
+            .fetch_add(count, Ordering::Relaxed);
+    }
+
+    /// Record an event-loop error for a backend or frontend (before the
+    /// supervisor restarts it).
+    pub fn record_event_loop_error(&self, component_id: &str) {
+        self.component(component_id)
+            .event_loop_errors
+            .fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_search(&self, frontend_id: &str, latency: Duration) {
+        let c = self.component(frontend_id);
+        c.search_queries.fetch_add(1, Ordering::Relaxed);
+        c.search_latency_ms_sum
+            .fetch_add(latency.as_millis() as u64, Ordering::Relaxed);
+    }
+
+    /// Record whether a pagination lookup (the query stashed in `Storage` for
+    /// a result message) was found or had already expired/was unknown.
+    pub fn record_pagination_cache(&self, frontend_id: &str, hit: bool) {
+        let c = self.component(frontend_id);
+        if hit {
+            c.pagination_cache_hits.fetch_add(1, Ordering::Relaxed);
+        } else {
+            c.pagination_cache_misses.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Render all counters in Prometheus text exposition format.
+    async fn render(&self) -> String {
+        let mut out = String::new();
+
+        let counter_families: &[(&str, &str, fn(&ComponentCounters) -> u64)] = &[
+            (
+                "tg_searcher_messages_indexed_total",
+                "Messages added to the index",
+                |c| c.messages_indexed.load(Ordering::Relaxed),
+            ),
+            (
+                "tg_searcher_messages_edited_total",
+                "Messages updated in the index",
+                |c| c.messages_edited.load(Ordering::Relaxed),
+            ),
+            (
+                "tg_searcher_messages_deleted_total",
+                "Messages removed from the index",
+                |c| c.messages_deleted.load(Ordering::Relaxed),
+            ),
+            (
+                "tg_searcher_event_loop_errors_total",
+                "Errors that caused a backend/frontend event loop to restart",
+                |c| c.event_loop_errors.load(Ordering::Relaxed),
+            ),
+            (
+                "tg_searcher_search_queries_total",
+                "Search queries served by a frontend",
+                |c| c.search_queries.load(Ordering::Relaxed),
+            ),
+            (
+                "tg_searcher_search_latency_ms_sum",
+                "Sum of search latencies in milliseconds (divide by tg_searcher_search_queries_total for the mean)",
+                |c| c.search_latency_ms_sum.load(Ordering::Relaxed),
+            ),
+            (
+                "tg_searcher_pagination_cache_hits_total",
+                "Pagination lookups that found the stashed query in storage",
+                |c| c.pagination_cache_hits.load(Ordering::Relaxed),
+            ),
+            (
+                "tg_searcher_pagination_cache_misses_total",
+                "Pagination lookups that found no stashed query in storage",
+                |c| c.pagination_cache_misses.load(Ordering::Relaxed),
+            ),
+        ];
+
+        let components = self.components.lock().unwrap().clone();
+        for (name, help, read) in counter_families {
+            out.push_str(&format!("# HELP {} {}\n", name, help));
+            out.push_str(&format!("# TYPE {} counter\n", name));
+            for (id, counters) in &components {
+                out.push_str(&format!(
+                    "{}{{component=\"{}\"}} {}\n",
+                    name,
+                    id,
+                    read(counters)
+                ));
+            }
+        }
+
+        out.push_str("# HELP tg_searcher_index_documents Documents currently in a backend's index\n");
+        out.push_str("# TYPE tg_searcher_index_documents gauge\n");
+        let indexers = self.indexers.lock().unwrap().clone();
+        for (id, indexer) in &indexers {
+            match indexer.num_docs().await {
+                Ok(n) => out.push_str(&format!(
+                    "tg_searcher_index_documents{{component=\"{}\"}} {}\n",
+                    id, n
+                )),
+                Err(e) => warn!("metrics: failed to read index size for '{}': {}", id, e),
+            }
+        }
+
+        out
+    }
+
+    /// Serve `/metrics` on `addr` until the process exits. Meant to be
+    /// spawned as its own task from `main()`; it has no shutdown hook since
+    /// it holds no state that needs flushing.
+    pub async fn serve(self, addr: SocketAddr) -> std::io::Result<()> {
+        let listener = TcpListener::bind(addr).await?;
+        info!("Metrics endpoint listening on http://{}/metrics", addr);
+
+        loop {
+            let (mut socket, _) = listener.accept().await?;
+            let metrics = self.clone();
+            tokio::spawn(async move {
+                let mut buf = [0u8; 1024];
+                // We only serve one endpoint, so the request is read and
+                // discarded rather than parsed.
+                if socket.read(&mut buf).await.is_err() {
+                    return;
+                }
+
+                let body = metrics.render().await;
+                let response = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    body.len(),
+                    body
+                );
+                let _ = socket.write_all(response.as_bytes()).await;
+            });
+        }
+    }
+}
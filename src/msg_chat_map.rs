@@ -0,0 +1,175 @@
+//! Persistent reverse lookup from Telegram message id to the chat(s) it
+//! was seen in.
+//!
+//! A `MessageDeletion` update for a private chat or basic group carries only
+//! a bare message id with no `channel_id`, so [`crate::backend::BackendBot`]
+//! cannot reconstruct which indexed document (keyed by `crate::utils::build_message_key`)
+//! was deleted from the deletion event alone. This module records
+//! `msg_id -> share_id` for every message the indexer ingests, populated
+//! alongside the URL construction in the add/update path, so a bare deletion
+//! can be resolved by looking up every chat on record for that id. Message
+//! ids are only unique within a chat, so a lookup can return several
+//! candidate share_ids; callers must narrow these down further (e.g. to
+//! currently monitored chats) before deleting.
+
+use crate::types::{Error, Result};
+use async_trait::async_trait;
+use dashmap::DashMap;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Backend-agnostic store mapping `msg_id -> [share_id]`.
+#[async_trait]
+pub trait MsgChatMapStore: Send + Sync {
+    /// Record that `msg_id` was seen in `share_id`.
+    async fn record(&self, msg_id: i32, share_id: i64) -> Result<()>;
+
+    /// Every chat on record for `msg_id`, in no particular order.
+    async fn lookup(&self, msg_id: i32) -> Result<Vec<i64>>;
+
+    /// Forget a single `(msg_id, share_id)` pairing, e.g. once the
+    /// corresponding document has been removed from the index.
+    async fn remove(&self, msg_id: i32, share_id: i64) -> Result<()>;
+}
+
+/// In-memory store backed by a [`DashMap`] (the historical behavior: no
+/// cross-restart resolution of non-channel deletions).
+#[derive(Clone, Default)]
+pub struct InMemMsgChatMapStore {
+    data: Arc<DashMap<i32, Vec<i64>>>,
+}
+
+impl InMemMsgChatMapStore {
+    /// Create an empty in-memory store.
+    pub fn new() -> Self {
+        Self {
+            data: Arc::new(DashMap::new()),
+        }
+    }
+}
+
+#[async_trait]
+impl MsgChatMapStore for InMemMsgChatMapStore {
+    async fn record(&self, msg_id: i32, share_id: i64) -> Result<()> {
+        let mut candidates = self.data.entry(msg_id).or_default();
+        if !candidates.contains(&share_id) {
+            candidates.push(share_id);
+        }
+        Ok(())
+    }
+
+    async fn lookup(&self, msg_id: i32) -> Result<Vec<i64>> {
+        Ok(self.data.get(&msg_id).map(|v| v.clone()).unwrap_or_default())
+    }
+
+    async fn remove(&self, msg_id: i32, share_id: i64) -> Result<()> {
+        if let Some(mut candidates) = self.data.get_mut(&msg_id) {
+            candidates.retain(|&id| id != share_id);
+            let is_empty = candidates.is_empty();
+            drop(candidates);
+            if is_empty {
+                self.data.remove(&msg_id);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// SQLite-backed store in the database file given to [`Self::open`]
+/// (`main` uses the backend's `state.db` in its index directory).
+///
+/// `(msg_id, share_id)` pairs live in a dedicated `msg_chat_map` table keyed
+/// by the pair itself, so recording the same message twice (e.g. a restart
+/// followed by a re-download) is a no-op rather than a growing duplicate list.
+pub struct SqliteMsgChatMapStore {
+    conn: tokio::sync::Mutex<rusqlite::Connection>,
+}
+
+impl SqliteMsgChatMapStore {
+    /// Open (creating if necessary) the lookup table in `db_path`.
+    pub fn open(db_path: &Path) -> Result<Self> {
+        let conn = rusqlite::Connection::open(db_path)
+            .map_err(|e| Error::Config(format!("Failed to open msg chat map db: {}", e)))?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS msg_chat_map (
+                msg_id   INTEGER NOT NULL,
+                share_id INTEGER NOT NULL,
+                PRIMARY KEY (msg_id, share_id)
+            )",
+            [],
+        )
+        .map_err(|e| Error::Config(format!("Failed to create msg_chat_map table: {}", e)))?;
+        Ok(Self {
+            conn: tokio::sync::Mutex::new(conn),
+        })
+    }
+}
+
+#[async_trait]
+impl MsgChatMapStore for SqliteMsgChatMapStore {
+    async fn record(&self, msg_id: i32, share_id: i64) -> Result<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            "INSERT OR IGNORE INTO msg_chat_map (msg_id, share_id) VALUES (?1, ?2)",
+            rusqlite::params![msg_id, share_id],
+        )
+        .map_err(|e| Error::Config(e.to_string()))?;
+        Ok(())
+    }
+
+    async fn lookup(&self, msg_id: i32) -> Result<Vec<i64>> {
+        let conn = self.conn.lock().await;
+        let mut stmt = conn
+            .prepare("SELECT share_id FROM msg_chat_map WHERE msg_id = ?1")
+            .map_err(|e| Error::Config(e.to_string()))?;
+        let rows = stmt
+            .query_map([msg_id], |row| row.get::<_, i64>(0))
+            .map_err(|e| Error::Config(e.to_string()))?;
+
+        let mut out = Vec::new();
+        for r in rows {
+            out.push(r.map_err(|e| Error::Config(e.to_string()))?);
+        }
+        Ok(out)
+    }
+
+    async fn remove(&self, msg_id: i32, share_id: i64) -> Result<()> {
+        let conn = self.conn.lock().await;
+        conn.execute(
+            "DELETE FROM msg_chat_map WHERE msg_id = ?1 AND share_id = ?2",
+            rusqlite::params![msg_id, share_id],
+        )
+        .map_err(|e| Error::Config(e.to_string()))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_in_mem_msg_chat_map_store() {
+        let store = InMemMsgChatMapStore::new();
+        assert!(store.lookup(100).await.unwrap().is_empty());
+
+        store.record(100, 42).await.unwrap();
+        assert_eq!(store.lookup(100).await.unwrap(), vec![42]);
+
+        // Recording the same pairing again must not duplicate it.
+        store.record(100, 42).await.unwrap();
+        assert_eq!(store.lookup(100).await.unwrap(), vec![42]);
+
+        // Id collisions across chats keep every candidate.
+        store.record(100, 7).await.unwrap();
+        let mut candidates = store.lookup(100).await.unwrap();
+        candidates.sort();
+        assert_eq!(candidates, vec![7, 42]);
+
+        store.remove(100, 42).await.unwrap();
+        assert_eq!(store.lookup(100).await.unwrap(), vec![7]);
+
+        store.remove(100, 7).await.unwrap();
+        assert!(store.lookup(100).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,125 @@
+//! A small async token-bucket rate limiter.
+//!
+//! Used by [`crate::backend::BackendBot::download_histories`] to keep the
+//! combined Telegram request rate of several concurrent chat downloads under
+//! the account-wide limit, and to let a `FLOOD_WAIT` hit by any one of them
+//! pause requests for all of them.
+
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+struct TokenBucketState {
+    tokens: f64,
+    last_refill: Instant,
+    paused_until: Option<Instant>,
+}
+
+/// Async token bucket: [`acquire`](TokenBucket::acquire) blocks until a token
+/// is available (refilled at `refill_per_sec`, capped at `capacity`), and
+/// [`pause_for`](TokenBucket::pause_for) lets an external `FLOOD_WAIT` signal
+/// hold back every waiter for a fixed duration.
+pub struct TokenBucket {
+    state: Mutex<TokenBucketState>,
+    capacity: f64,
+    refill_per_sec: f64,
+}
+
+impl TokenBucket {
+    pub fn new(capacity: f64, refill_per_sec: f64) -> Self {
+        Self {
+            state: Mutex::new(TokenBucketState {
+                tokens: capacity,
+                last_refill: Instant::now(),
+                paused_until: None,
+            }),
+            capacity,
+            refill_per_sec,
+        }
+    }
+
+    /// Wait until a request token is available, honoring any active pause.
+    pub async fn acquire(&self) {
+        loop {
+            let pending_pause = {
+                let mut state = self.state.lock().await;
+                match state.paused_until {
+                    Some(until) if Instant::now() < until => Some(until - Instant::now()),
+                    Some(_) => {
+                        state.paused_until = None;
+                        None
+                    }
+                    None => None,
+                }
+            };
+            if let Some(wait) = pending_pause {
+                tokio::time::sleep(wait).await;
+                continue;
+            }
+
+            let mut state = self.state.lock().await;
+            let now = Instant::now();
+            let elapsed = now.duration_since(state.last_refill).as_secs_f64();
+            state.tokens = (state.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+            state.last_refill = now;
+            if state.tokens >= 1.0 {
+                state.tokens -= 1.0;
+                return;
+            }
+            let wait_secs = (1.0 - state.tokens) / self.refill_per_sec;
+            drop(state);
+            tokio::time::sleep(Duration::from_secs_f64(wait_secs)).await;
+        }
+    }
+
+    /// Centrally pause the bucket for `secs` (e.g. on a `FLOOD_WAIT`), so
+    /// every concurrent caller waits out the same cooldown instead of
+    /// retrying independently. A shorter existing pause is extended, never
+    /// shortened.
+    pub async fn pause_for(&self, secs: u64) {
+        let mut state = self.state.lock().await;
+        let until = Instant::now() + Duration::from_secs(secs);
+        if state.paused_until.is_none_or(|existing| until > existing) {
+            state.paused_until = Some(until);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_acquire_refills_over_time() {
+        let bucket = TokenBucket::new(1.0, 1000.0);
+        bucket.acquire().await;
+        // Bucket started full with capacity 1, so the first acquire is
+        // immediate; the second must wait for a refill.
+        let start = Instant::now();
+        bucket.acquire().await;
+        assert!(start.elapsed() >= Duration::from_millis(1));
+    }
+
+    #[tokio::test]
+    async fn test_pause_for_blocks_acquire() {
+        let bucket = TokenBucket::new(10.0, 1000.0);
+        bucket.pause_for(0).await; // already-expired pause should not block
+        bucket.acquire().await;
+
+        bucket.pause_for(1).await;
+        let start = Instant::now();
+        // The pause is 1s; a much shorter timeout should still be pending.
+        tokio::time::timeout(Duration::from_millis(50), bucket.acquire())
+            .await
+            .expect_err("acquire should still be paused");
+        assert!(start.elapsed() >= Duration::from_millis(50));
+    }
+
+    #[tokio::test]
+    async fn test_pause_for_does_not_shorten_existing_pause() {
+        let bucket = TokenBucket::new(10.0, 1000.0);
+        bucket.pause_for(5).await;
+        bucket.pause_for(1).await; // shorter pause must not override the longer one
+        let paused_until = bucket.state.lock().await.paused_until.unwrap();
+        assert!(paused_until > Instant::now() + Duration::from_millis(500));
+    }
+}
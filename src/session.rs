@@ -2,9 +2,11 @@
 //!
 //! This module provides session storage and authentication helpers.
 
+use crate::chat_meta::{ChatMeta, ChatMetaStore, InMemChatMetaStore};
 use crate::config::ProxyConfig;
 use crate::types::{Error, Result};
 use crate::utils::get_share_id;
+use async_trait::async_trait;
 use dashmap::DashMap;
 use grammers_client::{Client, SignInError};
 use grammers_mtsender::{ConnectionParams, SenderPool};
@@ -12,8 +14,61 @@ use grammers_session::storages::SqliteSession;
 use std::io::Write;
 use std::path::Path;
 use std::sync::Arc;
+use std::time::Instant;
 use tracing::info;
 
+/// Source of interactive login secrets for [`ClientSession::start`].
+///
+/// Implementations supply the login code and, when 2FA is enabled, the
+/// account password. This lets login be driven from a terminal, a Telegram
+/// message to an already-authorized admin session, or an HTTP endpoint,
+/// rather than being hard-wired to `stdin`.
+#[async_trait]
+pub trait AuthProvider: Send + Sync {
+    /// Provide the verification code sent to `phone`.
+    async fn code(&self, phone: &str) -> Result<String>;
+
+    /// Provide the 2FA password, given the server-supplied `hint` if any.
+    async fn password(&self, hint: Option<&str>) -> Result<String>;
+}
+
+/// Built-in [`AuthProvider`] that prompts on the terminal (stderr + stdin),
+/// reading the password without echo via `rpassword`. This preserves the
+/// historical interactive behavior for users running the binary by hand.
+pub struct TerminalAuthProvider;
+
+#[async_trait]
+impl AuthProvider for TerminalAuthProvider {
+    async fn code(&self, phone: &str) -> Result<String> {
+        eprint!("Enter the verification code sent to {}: ", phone);
+        std::io::stderr().flush().map_err(Error::Io)?;
+
+        let mut code = String::new();
+        std::io::stdin().read_line(&mut code).map_err(Error::Io)?;
+        Ok(code.trim().to_string())
+    }
+
+    async fn password(&self, hint: Option<&str>) -> Result<String> {
+        let password = rpassword::prompt_password(format!(
+            "Enter your 2FA password (hint: {}): ",
+            hint.unwrap_or("None")
+        ))
+        .map_err(Error::Io)?;
+        Ok(password.trim().to_string())
+    }
+}
+
+/// Cached outcome of resolving a `@username` to a chat id (see
+/// `BackendBot::str_to_chat_id`). `Found` is kept until overwritten by a
+/// fresh resolution or explicitly invalidated; `NotFound` carries when it
+/// was cached so it can expire after a short TTL, so repeated bad input
+/// doesn't hammer the API forever but does get throttled.
+#[derive(Debug, Clone, Copy)]
+pub enum UsernameCacheEntry {
+    Found(i64),
+    NotFound(Instant),
+}
+
 /// Telegram session configuration
 pub struct ClientSession {
     /// Session name for logging
@@ -33,6 +88,14 @@ pub struct ClientSession {
 
     /// Chat ID to name cache (populated during access hash population)
     chat_cache: Arc<DashMap<i64, String>>,
+
+    /// Username to chat ID cache, positive and short-lived-negative (see
+    /// [`UsernameCacheEntry`]), populated alongside `chat_cache` during
+    /// access hash population.
+    username_cache: Arc<DashMap<String, UsernameCacheEntry>>,
+
+    /// Persistent store for chat metadata (names, types, last-seen)
+    meta_store: Arc<dyn ChatMetaStore>,
 }
 
 impl ClientSession {
@@ -85,11 +148,28 @@ impl ClientSession {
             api_hash: api_hash.to_string(),
             proxy: proxy_url,
             chat_cache: Arc::new(DashMap::new()),
+            username_cache: Arc::new(DashMap::new()),
+            meta_store: Arc::new(InMemChatMetaStore::new()),
         })
     }
 
-    /// Authenticate the session if needed
-    pub async fn start(&self, phone: &str) -> Result<()> {
+    /// Replace the chat-metadata store (e.g. with a persistent SQLite-backed
+    /// one). Defaults to [`InMemChatMetaStore`] after [`ClientSession::new`].
+    pub fn set_meta_store(&mut self, store: Arc<dyn ChatMetaStore>) {
+        self.meta_store = store;
+    }
+
+    /// Access the chat-metadata store.
+    pub fn meta_store(&self) -> Arc<dyn ChatMetaStore> {
+        Arc::clone(&self.meta_store)
+    }
+
+    /// Authenticate the session if needed, collecting secrets through `auth`.
+    ///
+    /// The runner-spawn/abort lifecycle is unchanged; the provider is awaited
+    /// at each step so login can be driven headlessly (e.g. code delivered
+    /// out-of-band while the `LoginToken` is held).
+    pub async fn start(&self, phone: &str, auth: &dyn AuthProvider) -> Result<()> {
         info!("Authenticating session: {}", self.name);
 
         // Create temporary client for authentication
@@ -126,12 +206,8 @@ impl ClientSession {
             .await
             .map_err(|e| Error::Telegram(format!("Failed to request login code: {}", e)))?;
 
-        // Prompt for code
-        eprint!("Enter the verification code sent to {}: ", phone);
-        std::io::stderr().flush().map_err(Error::Io)?;
-
-        let mut code = String::new();
-        std::io::stdin().read_line(&mut code).map_err(Error::Io)?;
+        // Collect the code through the provider (may arrive out-of-band)
+        let code = auth.code(phone).await?;
 
         // Sign in with code
         match client.sign_in(&token, code.trim()).await {
@@ -139,13 +215,9 @@ impl ClientSession {
                 info!("Signed in successfully");
             }
             Err(SignInError::PasswordRequired(password_token)) => {
-                // 2FA required
-                let hint = password_token.hint().unwrap_or("None");
-                let password = rpassword::prompt_password(format!(
-                    "Enter your 2FA password (hint: {}): ",
-                    hint
-                ))
-                .map_err(Error::Io)?;
+                // 2FA required: surface the server hint through the provider
+                let hint = password_token.hint().map(|s| s.to_string());
+                let password = auth.password(hint.as_deref()).await?;
 
                 client
                     .check_password(password_token, password.trim())
@@ -176,6 +248,21 @@ impl ClientSession {
             self.name
         );
 
+        // If the persistent store already holds a snapshot, hydrate the
+        // in-memory cache from it and skip the slow full dialog scan.
+        let snapshot = self.meta_store.all().await?;
+        if !snapshot.is_empty() {
+            for (share_id, meta) in &snapshot {
+                self.chat_cache.insert(*share_id, meta.name.clone());
+            }
+            info!(
+                "Loaded {} chats from persistent store for session {} (skipping dialog scan)",
+                snapshot.len(),
+                self.name
+            );
+            return Ok(snapshot.len());
+        }
+
         // Create temporary client for fetching dialogs
         let pool = if let Some(ref proxy_url) = self.proxy {
             let params = ConnectionParams {
@@ -208,6 +295,25 @@ impl ClientSession {
             let share_id = get_share_id(chat_id);
             if let Some(name) = peer.name() {
                 self.chat_cache.insert(share_id, name.to_string());
+                // Upsert into the persistent store so the snapshot survives
+                // across restarts.
+                let meta = ChatMeta {
+                    name: name.to_string(),
+                    chat_type: match crate::utils::resolve_id(chat_id).1 {
+                        crate::utils::PeerType::User => "user",
+                        crate::utils::PeerType::Chat => "group",
+                        crate::utils::PeerType::Channel => "channel",
+                    }
+                    .to_string(),
+                    last_seen: chrono::Utc::now().timestamp(),
+                };
+                if let Err(e) = self.meta_store.insert(share_id, meta).await {
+                    tracing::warn!("Failed to persist chat meta for {}: {}", share_id, e);
+                }
+            }
+            if let Some(username) = peer.username() {
+                self.username_cache
+                    .insert(username.to_string(), UsernameCacheEntry::Found(share_id));
             }
         }
 
@@ -224,6 +330,27 @@ impl ClientSession {
         Ok(dialog_count)
     }
 
+    /// Path of the sidecar file holding the resumable update offset.
+    fn update_offset_path(&self) -> std::path::PathBuf {
+        std::path::PathBuf::from(format!("{}.updstate", self.name))
+    }
+
+    /// Load the last persisted update offset, if any. Grammers persists the
+    /// detailed update state in its own session DB; this sidecar records a
+    /// coarse monotonic counter used for logging/diagnostics.
+    pub fn load_update_offset(&self) -> Option<i64> {
+        std::fs::read_to_string(self.update_offset_path())
+            .ok()
+            .and_then(|s| s.trim().parse().ok())
+    }
+
+    /// Increment and persist the coarse update offset counter for this
+    /// session. See [`ClientSession::load_update_offset`] for what this is
+    /// (and isn't) used for.
+    pub fn persist_update_offset(&self) {
+        persist_update_offset(&self.update_offset_path());
+    }
+
     /// Get session name
     pub fn name(&self) -> &str {
         &self.name
@@ -253,4 +380,19 @@ impl ClientSession {
     pub fn chat_cache(&self) -> Arc<DashMap<i64, String>> {
         Arc::clone(&self.chat_cache)
     }
+
+    /// Get username resolution cache
+    pub fn username_cache(&self) -> Arc<DashMap<String, UsernameCacheEntry>> {
+        Arc::clone(&self.username_cache)
+    }
+}
+
+/// Increment and persist the coarse update offset counter.
+fn persist_update_offset(path: &Path) {
+    let next = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|s| s.trim().parse::<i64>().ok())
+        .unwrap_or(0)
+        + 1;
+    let _ = std::fs::write(path, next.to_string());
 }
@@ -0,0 +1,259 @@
+//! Downstream event sinks for indexed messages
+//!
+//! `BackendBot` fans every successful index write out to the sinks
+//! configured on its `BackendBotConfig`, the way a blockchain indexer
+//! streams decoded events to webhooks/queues. A sink failure is logged and
+//! never aborts indexing — see the call sites in `backend.rs`.
+
+use crate::config::{SinkConfig, SinkFilterConfig};
+use crate::types::{Error, IndexMsg, Result};
+use async_trait::async_trait;
+use std::collections::HashSet;
+use std::time::Duration;
+
+/// Kind of change that produced an [`IndexEvent`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexEventKind {
+    Created,
+    Edited,
+    Deleted,
+}
+
+/// An indexing event published to configured sinks. Deletion events only
+/// carry `msg.url`/`msg.chat_id` (content/sender are left empty), since
+/// that's all `handle_message_deleted` has to hand.
+#[derive(Debug, Clone)]
+pub struct IndexEvent {
+    pub kind: IndexEventKind,
+    pub msg: IndexMsg,
+}
+
+/// A downstream destination for indexing events.
+#[async_trait]
+pub trait EventSink: Send + Sync {
+    async fn publish(&self, event: &IndexEvent) -> Result<()>;
+}
+
+/// Chat-id allowlist / content substring-or-regex filter evaluated before
+/// publishing to a sink. All set conditions must match.
+pub struct SinkFilter {
+    chat_ids: Option<HashSet<i64>>,
+    content_contains: Option<String>,
+    content_regex: Option<regex::Regex>,
+}
+
+impl SinkFilter {
+    pub fn from_config(config: &SinkFilterConfig) -> Result<Self> {
+        let content_regex = config
+            .content_regex
+            .as_deref()
+            .map(regex::Regex::new)
+            .transpose()
+            .map_err(|e| Error::Config(format!("Invalid sink content_regex: {}", e)))?;
+
+        Ok(Self {
+            chat_ids: config.chat_ids.clone(),
+            content_contains: config.content_contains.clone(),
+            content_regex,
+        })
+    }
+
+    pub fn matches(&self, event: &IndexEvent) -> bool {
+        if let Some(chat_ids) = &self.chat_ids
+            && !chat_ids.contains(&event.msg.chat_id)
+        {
+            return false;
+        }
+        if let Some(substr) = &self.content_contains
+            && !event.msg.content.contains(substr.as_str())
+        {
+            return false;
+        }
+        if let Some(re) = &self.content_regex
+            && !re.is_match(&event.msg.content)
+        {
+            return false;
+        }
+        true
+    }
+}
+
+/// Request timeout for [`WebhookSink::publish`], so a stalled or
+/// slow-walking receiver can't back up indexing behind it.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// POSTs each event as JSON to a configured URL.
+///
+/// Built on `reqwest` (already a crate dependency for
+/// `link_enrich::fetch_enrichment`) rather than a hand-rolled
+/// `tokio::net::TcpStream` request, so `https://` sink URLs — the common
+/// case for real webhook receivers — actually get a TLS handshake.
+pub struct WebhookSink {
+    filter: SinkFilter,
+    url: url::Url,
+    client: reqwest::Client,
+}
+
+impl WebhookSink {
+    pub fn new(url: &str, filter_config: &SinkFilterConfig) -> Result<Self> {
+        let url = url::Url::parse(url)
+            .map_err(|e| Error::Config(format!("Invalid webhook sink URL '{}': {}", url, e)))?;
+        let client = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()
+            .map_err(|e| Error::Config(format!("Failed to build webhook client: {}", e)))?;
+        Ok(Self {
+            filter: SinkFilter::from_config(filter_config)?,
+            url,
+            client,
+        })
+    }
+
+    fn body(event: &IndexEvent) -> String {
+        #[derive(serde::Serialize)]
+        struct EventPayload<'a> {
+            kind: &'a str,
+            chat_id: i64,
+            url: &'a str,
+            content: &'a str,
+            sender: &'a str,
+            post_time: chrono::DateTime<chrono::Utc>,
+        }
+
+        let kind = match event.kind {
+            IndexEventKind::Created => "created",
+            IndexEventKind::Edited => "edited",
+            IndexEventKind::Deleted => "deleted",
+        };
+        let payload = EventPayload {
+            kind,
+            chat_id: event.msg.chat_id,
+            url: &event.msg.url,
+            content: &event.msg.content,
+            sender: &event.msg.sender,
+            post_time: event.msg.post_time,
+        };
+        serde_json::to_string(&payload).unwrap_or_else(|_| "{}".to_string())
+    }
+}
+
+#[async_trait]
+impl EventSink for WebhookSink {
+    async fn publish(&self, event: &IndexEvent) -> Result<()> {
+        if !self.filter.matches(event) {
+            return Ok(());
+        }
+
+        let body = Self::body(event);
+        let response = self
+            .client
+            .post(self.url.clone())
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .body(body)
+            .send()
+            .await
+            .map_err(|e| Error::Other(anyhow::anyhow!("Webhook sink request failed: {}", e)))?;
+
+        if !response.status().is_success() {
+            return Err(Error::Other(anyhow::anyhow!(
+                "Webhook sink '{}' returned HTTP {}",
+                self.url,
+                response.status()
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+/// Publishes each event to an AMQP exchange. Only available when built with
+/// the `amqp-sink` feature (pulls in an AMQP client, which most deployments
+/// don't need).
+#[cfg(feature = "amqp-sink")]
+pub struct AmqpSink {
+    filter: SinkFilter,
+    exchange: String,
+    routing_key: String,
+    channel: lapin::Channel,
+}
+
+#[cfg(feature = "amqp-sink")]
+impl AmqpSink {
+    pub async fn connect(
+        url: &str,
+        exchange: String,
+        routing_key: String,
+        filter_config: &SinkFilterConfig,
+    ) -> Result<Self> {
+        let connection = lapin::Connection::connect(url, lapin::ConnectionProperties::default())
+            .await
+            .map_err(|e| Error::Config(format!("Failed to connect to AMQP broker: {}", e)))?;
+        let channel = connection
+            .create_channel()
+            .await
+            .map_err(|e| Error::Config(format!("Failed to open AMQP channel: {}", e)))?;
+        Ok(Self {
+            filter: SinkFilter::from_config(filter_config)?,
+            exchange,
+            routing_key,
+            channel,
+        })
+    }
+}
+
+#[cfg(feature = "amqp-sink")]
+#[async_trait]
+impl EventSink for AmqpSink {
+    async fn publish(&self, event: &IndexEvent) -> Result<()> {
+        if !self.filter.matches(event) {
+            return Ok(());
+        }
+
+        let body = WebhookSink::body(event);
+        self.channel
+            .basic_publish(
+                &self.exchange,
+                &self.routing_key,
+                lapin::options::BasicPublishOptions::default(),
+                body.as_bytes(),
+                lapin::BasicProperties::default(),
+            )
+            .await
+            .map_err(|e| Error::Other(anyhow::anyhow!("AMQP publish failed: {}", e)))?;
+        Ok(())
+    }
+}
+
+/// Build the sinks configured for a backend. An `Amqp` entry in a binary
+/// built without the `amqp-sink` feature is a hard config error rather than
+/// a silent skip.
+pub async fn build_sinks(configs: &[SinkConfig]) -> Result<Vec<Box<dyn EventSink>>> {
+    let mut sinks: Vec<Box<dyn EventSink>> = Vec::with_capacity(configs.len());
+    for config in configs {
+        match config {
+            SinkConfig::Webhook { url, filter } => {
+                sinks.push(Box::new(WebhookSink::new(url, filter)?));
+            }
+            #[cfg(feature = "amqp-sink")]
+            SinkConfig::Amqp {
+                url,
+                exchange,
+                routing_key,
+                filter,
+            } => {
+                sinks.push(Box::new(
+                    AmqpSink::connect(url, exchange.clone(), routing_key.clone(), filter).await?,
+                ));
+            }
+            #[cfg(not(feature = "amqp-sink"))]
+            SinkConfig::Amqp { .. } => {
+                return Err(Error::Config(
+                    "An 'amqp' sink is configured but this binary was built without the \
+                     'amqp-sink' feature"
+                        .to_string(),
+                ));
+            }
+        }
+    }
+    Ok(sinks)
+}
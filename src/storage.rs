@@ -3,8 +3,10 @@
 //! This module provides a storage interface that can be implemented
 //! with different backends (in-memory, Redis, etc.)
 
+use crate::types::{Error, Result};
 use async_trait::async_trait;
 use dashmap::DashMap;
+use redis::AsyncCommands;
 use std::sync::Arc;
 
 /// Storage trait for persisting bot state
@@ -68,6 +70,90 @@ impl Storage for InMemoryStorage {
     }
 }
 
+/// Redis-backed storage implementation.
+///
+/// Keys are namespaced under `key_prefix` so several frontends can share one
+/// Redis database without colliding, and persist across restarts so multiple
+/// frontend instances can coordinate pagination against the same store. An
+/// optional TTL is applied to every write, useful for expiring stale
+/// pagination entries instead of accumulating them forever.
+#[derive(Clone)]
+pub struct RedisStorage {
+    conn: redis::aio::ConnectionManager,
+    key_prefix: String,
+    ttl_secs: Option<u64>,
+}
+
+impl RedisStorage {
+    /// Connect to `url` and return a storage pooled over a single
+    /// auto-reconnecting [`redis::aio::ConnectionManager`].
+    pub async fn connect(url: &str, key_prefix: String, ttl_secs: Option<u64>) -> Result<Self> {
+        let client = redis::Client::open(url)
+            .map_err(|e| Error::Config(format!("Invalid Redis URL: {}", e)))?;
+        let conn = client
+            .get_connection_manager()
+            .await
+            .map_err(|e| Error::Config(format!("Failed to connect to Redis: {}", e)))?;
+        Ok(Self {
+            conn,
+            key_prefix,
+            ttl_secs,
+        })
+    }
+
+    /// Prefix `key` with the configured namespace.
+    fn namespaced(&self, key: &str) -> String {
+        format!("{}:{}", self.key_prefix, key)
+    }
+}
+
+#[async_trait]
+impl Storage for RedisStorage {
+    async fn set(&self, key: &str, value: &str) -> crate::types::Result<()> {
+        let mut conn = self.conn.clone();
+        let full_key = self.namespaced(key);
+        match self.ttl_secs {
+            Some(ttl) => conn.set_ex::<_, _, ()>(&full_key, value, ttl).await,
+            None => conn.set::<_, _, ()>(&full_key, value).await,
+        }
+        .map_err(|e| Error::Config(format!("Redis SET failed: {}", e)))?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> crate::types::Result<Option<String>> {
+        let mut conn = self.conn.clone();
+        let full_key = self.namespaced(key);
+        conn.get(&full_key)
+            .await
+            .map_err(|e| Error::Config(format!("Redis GET failed: {}", e)))
+    }
+
+    async fn delete(&self, key: &str) -> crate::types::Result<()> {
+        let mut conn = self.conn.clone();
+        let full_key = self.namespaced(key);
+        conn.del::<_, ()>(&full_key)
+            .await
+            .map_err(|e| Error::Config(format!("Redis DEL failed: {}", e)))?;
+        Ok(())
+    }
+
+    async fn clear(&self) -> crate::types::Result<()> {
+        // Only sweep our own namespace so we don't nuke the whole database.
+        let mut conn = self.conn.clone();
+        let pattern = format!("{}:*", self.key_prefix);
+        let keys: Vec<String> = conn
+            .keys(&pattern)
+            .await
+            .map_err(|e| Error::Config(format!("Redis KEYS failed: {}", e)))?;
+        if !keys.is_empty() {
+            conn.del::<_, ()>(keys)
+                .await
+                .map_err(|e| Error::Config(format!("Redis DEL failed: {}", e)))?;
+        }
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
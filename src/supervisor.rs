@@ -0,0 +1,104 @@
+//! Task supervision for backend/frontend event loops
+//!
+//! Wraps a fallible, restartable event loop (`BackendBot::run`,
+//! `BotFrontend::run`) with exponential-backoff restarts, so a component
+//! that errors out gets retried instead of leaving its `JoinHandle` dead.
+//! Each component's current lifecycle state is tracked in a small registry
+//! keyed by id.
+
+use crate::types::Result;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+use tracing::{info, warn};
+
+/// Initial delay before the first restart attempt.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// Cap on the exponential backoff so a persistently-failing component still
+/// retries periodically instead of giving up.
+const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Current lifecycle state of a supervised component.
+#[derive(Debug, Clone)]
+pub enum ComponentState {
+    /// Event loop is running normally.
+    Running,
+    /// Event loop errored out; waiting to restart.
+    Restarting { attempt: u32, last_error: String },
+    /// Stopped for good (graceful shutdown, or the loop returned `Ok`).
+    Stopped,
+}
+
+/// Shared registry of supervised components' states, keyed by component id.
+#[derive(Clone, Default)]
+pub struct Registry {
+    states: Arc<Mutex<HashMap<String, ComponentState>>>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn set(&self, id: &str, state: ComponentState) {
+        self.states.lock().unwrap().insert(id.to_string(), state);
+    }
+
+    /// Snapshot of every tracked component's current state.
+    #[allow(dead_code)]
+    pub fn snapshot(&self) -> HashMap<String, ComponentState> {
+        self.states.lock().unwrap().clone()
+    }
+}
+
+/// Run `run_once` in a loop, restarting with exponential backoff whenever it
+/// returns `Err`. `run_once` is expected to return `Ok(())` only when it
+/// observed `shutdown` and stopped on its own (as `BackendBot::run` and
+/// `BotFrontend::run` do); once it does, supervision ends for good.
+/// Records `id`'s lifecycle in `registry`.
+pub async fn supervise<F, Fut>(id: String, registry: Registry, shutdown: Arc<Notify>, mut run_once: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<()>>,
+{
+    registry.set(&id, ComponentState::Running);
+    let mut backoff = INITIAL_BACKOFF;
+    let mut attempt: u32 = 0;
+
+    loop {
+        match run_once().await {
+            Ok(()) => {
+                info!("Component '{}' stopped", id);
+                registry.set(&id, ComponentState::Stopped);
+                return;
+            }
+            Err(e) => {
+                attempt += 1;
+                warn!(
+                    "Component '{}' event loop error (attempt {}): {}; restarting in {:?}",
+                    id, attempt, e, backoff
+                );
+                registry.set(
+                    &id,
+                    ComponentState::Restarting {
+                        attempt,
+                        last_error: e.to_string(),
+                    },
+                );
+            }
+        }
+
+        tokio::select! {
+            _ = tokio::time::sleep(backoff) => {}
+            _ = shutdown.notified() => {
+                info!("Component '{}' supervisor stopping during backoff", id);
+                registry.set(&id, ComponentState::Stopped);
+                return;
+            }
+        }
+        backoff = (backoff * 2).min(MAX_BACKOFF);
+        registry.set(&id, ComponentState::Running);
+    }
+}
@@ -36,6 +36,47 @@ pub enum Error {
 /// Result type alias
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Media kind of an indexed message. Lets a captionless photo/video/document
+/// still be found (e.g. "that PDF someone posted") and, later, filtered by
+/// type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MediaType {
+    Text,
+    Photo,
+    Video,
+    Document,
+    Audio,
+    Sticker,
+}
+
+impl MediaType {
+    /// Stable lowercase name used as the stored Tantivy field value.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MediaType::Text => "text",
+            MediaType::Photo => "photo",
+            MediaType::Video => "video",
+            MediaType::Document => "document",
+            MediaType::Audio => "audio",
+            MediaType::Sticker => "sticker",
+        }
+    }
+
+    /// Parse a stored field value back into a `MediaType`, defaulting to
+    /// `Text` for anything unrecognized (e.g. an index written before this
+    /// field existed).
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "photo" => MediaType::Photo,
+            "video" => MediaType::Video,
+            "document" => MediaType::Document,
+            "audio" => MediaType::Audio,
+            "sticker" => MediaType::Sticker,
+            _ => MediaType::Text,
+        }
+    }
+}
+
 /// Message to be indexed
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IndexMsg {
@@ -53,6 +94,9 @@ pub struct IndexMsg {
 
     /// Sender's name
     pub sender: String,
+
+    /// Media kind (`Text` for ordinary messages), for later type filtering.
+    pub media_type: MediaType,
 }
 
 /// Search result hit with highlighting
@@ -63,6 +107,9 @@ pub struct SearchHit {
 
     /// Highlighted content (HTML with highlights)
     pub highlighted: String,
+
+    /// BM25 relevance score from Tantivy, highest first within a page
+    pub score: f32,
 }
 
 /// Search results with pagination info
@@ -76,6 +123,48 @@ pub struct SearchResult {
 
     /// Total number of results
     pub total_results: usize,
+
+    /// "Did you mean" suggestion built from the closest in-dictionary terms,
+    /// populated by `MatchMode::Fuzzy` searches that returned few hits.
+    pub suggestion: Option<String>,
+}
+
+/// Result of [`crate::indexer::Indexer::search_with_correction`]: the
+/// original query's hit count alongside results for whichever query
+/// (original or spelling-corrected) actually produced them.
+#[derive(Debug, Clone)]
+pub struct CorrectedSearchResult {
+    /// Hit count from the original, uncorrected query.
+    pub original_total_results: usize,
+
+    /// Suggested corrected query string, if the term dictionary offered one.
+    pub suggestion: Option<String>,
+
+    /// Results for the corrected query, or for the original query
+    /// unchanged if no correction was found or needed.
+    pub result: SearchResult,
+}
+
+
+/// Outcome of a `download_history` run.
+///
+/// Carries enough information for the caller to report what happened (how many
+/// messages were added versus skipped) and to record a resumable checkpoint
+/// (the lowest/highest message ids that were processed).
+#[derive(Debug, Clone, Default)]
+pub struct DownloadOutcome {
+    /// Total messages fetched from Telegram within the requested range.
+    pub fetched: usize,
+
+    /// Messages actually added to the index (those carrying indexable text).
+    pub indexed: usize,
+
+    /// Lowest message id processed, or 0 if nothing was fetched. This is the
+    /// resume checkpoint for continuing the backward pagination.
+    pub lowest_msg_id: i32,
+
+    /// Highest message id processed, or 0 if nothing was fetched.
+    pub highest_msg_id: i32,
 }
 
 /// Progress update for download_history operation
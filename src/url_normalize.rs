@@ -0,0 +1,180 @@
+//! Canonicalize AMP-mirror and tracker-laden URLs before they're indexed,
+//! so a message linking an AMP page or a tracking-parameter variant of a
+//! URL is still found by someone searching the canonical link.
+//!
+//! Detection is host-based (a configured list of AMP cache hosts plus the
+//! common publisher convention of an `/amp` path segment) rather than a
+//! broad regex over arbitrary text, per linkleaner's approach. The lists
+//! below are just a baseline — operators extend them via
+//! `BackendBotConfig::url_normalize`. Any URL that fails to parse, or that
+//! doesn't match a known pattern, is left untouched rather than guessed at.
+
+use crate::config::UrlNormalizeConfig;
+
+/// Hostname suffixes for known AMP cache/mirror services.
+const DEFAULT_AMP_HOSTS: &[&str] = &["cdn.ampproject.org", "amp.cloudflare.com"];
+
+/// Tracking query parameter patterns stripped by default. A trailing `*`
+/// matches any suffix (e.g. `utm_*` matches `utm_source`, `utm_medium`, ...).
+const DEFAULT_TRACKING_PARAMS: &[&str] = &["utm_*", "fbclid", "gclid", "igshid", "mc_eid"];
+
+/// The result of normalizing a single URL found in message text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NormalizedUrl {
+    /// The URL exactly as it appeared in the message.
+    pub original: String,
+
+    /// Its canonical form, if normalization changed anything. `None` when
+    /// the URL was already canonical (no tracking params, not an AMP
+    /// mirror) or couldn't be parsed.
+    pub canonical: Option<String>,
+}
+
+/// Strip tracking query parameters and resolve AMP mirrors to their
+/// canonical form. Returns `canonical: None` if `url` fails to parse or
+/// normalization made no change.
+pub fn normalize_url(url: &str, config: &UrlNormalizeConfig) -> NormalizedUrl {
+    let Ok(parsed) = url::Url::parse(url) else {
+        return NormalizedUrl {
+            original: url.to_string(),
+            canonical: None,
+        };
+    };
+
+    let stripped = strip_tracking_params(&parsed, config);
+    let canonical = de_amp(&stripped, config).unwrap_or(stripped);
+
+    NormalizedUrl {
+        canonical: (canonical != url).then_some(canonical),
+        original: url.to_string(),
+    }
+}
+
+/// Remove query parameters matching any of the default or configured
+/// tracking-param patterns, returning the resulting URL as a string.
+fn strip_tracking_params(parsed: &url::Url, config: &UrlNormalizeConfig) -> String {
+    if parsed.query().is_none() {
+        return parsed.to_string();
+    }
+
+    let patterns: Vec<&str> = DEFAULT_TRACKING_PARAMS
+        .iter()
+        .copied()
+        .chain(config.tracking_params.iter().map(String::as_str))
+        .collect();
+    let kept: Vec<(String, String)> = parsed
+        .query_pairs()
+        .filter(|(key, _)| !patterns.iter().any(|pattern| param_matches(pattern, key)))
+        .map(|(key, value)| (key.into_owned(), value.into_owned()))
+        .collect();
+
+    let mut result = parsed.clone();
+    if kept.is_empty() {
+        result.set_query(None);
+    } else {
+        result.query_pairs_mut().clear().extend_pairs(&kept);
+    }
+    result.to_string()
+}
+
+fn param_matches(pattern: &str, key: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => key.starts_with(prefix),
+        None => key == pattern,
+    }
+}
+
+/// Resolve an AMP mirror to its canonical URL via a host-based transform:
+/// known AMP cache hosts encode the original scheme/host/path after a
+/// `/c/s/` (https) or `/c/` (http) path prefix, and many publishers mark
+/// their own AMP pages with a trailing `/amp` path segment that simply
+/// drops off to reach the canonical page. Returns `None` when `url` isn't
+/// recognized as an AMP variant.
+fn de_amp(url: &str, config: &UrlNormalizeConfig) -> Option<String> {
+    let parsed = url::Url::parse(url).ok()?;
+    let host = parsed.host_str()?;
+
+    let is_amp_cache_host = DEFAULT_AMP_HOSTS
+        .iter()
+        .copied()
+        .chain(config.amp_hosts.iter().map(String::as_str))
+        .any(|amp_host| host == amp_host || host.ends_with(&format!(".{}", amp_host)));
+    if is_amp_cache_host {
+        let rest = parsed
+            .path()
+            .strip_prefix("/c/s/")
+            .map(|rest| format!("https://{}", rest))
+            .or_else(|| {
+                parsed
+                    .path()
+                    .strip_prefix("/c/")
+                    .map(|rest| format!("http://{}", rest))
+            })?;
+        return Some(rest);
+    }
+
+    let path = parsed.path();
+    let trimmed_path = path.strip_suffix("/amp").or_else(|| path.strip_suffix("/amp/"))?;
+    let mut canonical = parsed.clone();
+    canonical.set_path(if trimmed_path.is_empty() { "/" } else { trimmed_path });
+    Some(canonical.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strips_default_tracking_params() {
+        let config = UrlNormalizeConfig::default();
+        let result = normalize_url(
+            "https://example.com/article?utm_source=tg&utm_medium=share&id=42",
+            &config,
+        );
+        assert_eq!(
+            result.canonical.as_deref(),
+            Some("https://example.com/article?id=42")
+        );
+    }
+
+    #[test]
+    fn test_strips_configured_tracking_params() {
+        let config = UrlNormalizeConfig {
+            amp_hosts: vec![],
+            tracking_params: vec!["ref_src".to_string()],
+        };
+        let result = normalize_url("https://example.com/page?ref_src=tg", &config);
+        assert_eq!(result.canonical.as_deref(), Some("https://example.com/page"));
+    }
+
+    #[test]
+    fn test_resolves_amp_cache_host() {
+        let config = UrlNormalizeConfig::default();
+        let result = normalize_url(
+            "https://example-com.cdn.ampproject.org/c/s/example.com/article",
+            &config,
+        );
+        assert_eq!(result.canonical.as_deref(), Some("https://example.com/article"));
+    }
+
+    #[test]
+    fn test_resolves_trailing_amp_path_segment() {
+        let config = UrlNormalizeConfig::default();
+        let result = normalize_url("https://example.com/article/amp", &config);
+        assert_eq!(result.canonical.as_deref(), Some("https://example.com/article"));
+    }
+
+    #[test]
+    fn test_leaves_canonical_url_untouched() {
+        let config = UrlNormalizeConfig::default();
+        let result = normalize_url("https://example.com/article?id=42", &config);
+        assert_eq!(result.canonical, None);
+    }
+
+    #[test]
+    fn test_leaves_unparseable_url_untouched() {
+        let config = UrlNormalizeConfig::default();
+        let result = normalize_url("not a url", &config);
+        assert_eq!(result.canonical, None);
+    }
+}
@@ -1,7 +1,18 @@
 //! Utility functions for TG Searcher
 
+use grammers_client::InvocationError;
 use html_escape::encode_text;
 
+/// If `err` is a `FLOOD_WAIT` RPC error, the number of seconds Telegram asked
+/// us to wait before retrying. Shared by any call site that retries RPCs
+/// (message send/edit in `frontend.rs`, history iteration in `backend.rs`).
+pub fn flood_wait_secs(err: &InvocationError) -> Option<u64> {
+    match err {
+        InvocationError::Rpc(rpc) if rpc.name == "FLOOD_WAIT" => rpc.value.map(u64::from),
+        _ => None,
+    }
+}
+
 /// Escape HTML content and replace newlines with spaces
 pub fn escape_content(content: &str) -> String {
     encode_text(content).replace('\n', " ")
@@ -28,32 +39,177 @@ pub fn remove_first_word(text: &str) -> &str {
     }
 }
 
-/// Get normalized share ID from Telegram chat ID
+/// Score a fuzzy match of `query` against a candidate `title`.
 ///
-/// Telegram uses different ID formats for different chat types.
-/// This function normalizes them to the share ID format used in URLs.
+/// The query is split on whitespace into terms; every term must appear as an
+/// in-order subsequence of the (lowercased) title, otherwise the candidate is
+/// rejected (`None`). This is the behavior users expect from contact search in
+/// chat clients: `proj rust` matches `Rust Project Discussion` regardless of
+/// word order.
+///
+/// Each term's subsequence score rewards consecutive matches, matches at word
+/// boundaries (title start or after a space / `_` / `-`) and matches near the
+/// start of the title, and penalizes the gaps skipped between matched
+/// characters. The returned score is the sum over all terms; higher is better.
+///
+/// Iteration is over Unicode scalar values, not bytes, so CJK titles score
+/// correctly.
+pub fn fuzzy_match_score(query: &str, title: &str) -> Option<i64> {
+    let title_chars: Vec<char> = title.to_lowercase().chars().collect();
+
+    let mut total = 0i64;
+    let mut matched_any = false;
+    for term in query.split_whitespace() {
+        total += fuzzy_term_score(&term.to_lowercase(), &title_chars)?;
+        matched_any = true;
+    }
+
+    // An all-whitespace query has no terms; treat it as no match.
+    matched_any.then_some(total)
+}
+
+/// Score a single lowercased `term` as an in-order subsequence of `title`
+/// (already lowercased into scalar values). Returns `None` if some term
+/// character cannot be matched in order.
+fn fuzzy_term_score(term: &str, title: &[char]) -> Option<i64> {
+    const MATCH: i64 = 10;
+    const CONSECUTIVE_BONUS: i64 = 15;
+    const BOUNDARY_BONUS: i64 = 20;
+    const START_BONUS: i64 = 10;
+    const GAP_PENALTY: i64 = 2;
+    const MAX_GAP_PENALTY: i64 = 20;
+
+    let mut score = 0i64;
+    let mut cursor = 0usize;
+    let mut prev_match: Option<usize> = None;
+
+    for qc in term.chars() {
+        // Advance through the title looking for the next occurrence of `qc`.
+        let found = title[cursor..].iter().position(|&tc| tc == qc)?;
+        let idx = cursor + found;
+
+        score += MATCH;
+
+        // Reward matches adjacent to the previous one, penalize gaps.
+        match prev_match {
+            Some(prev) if idx == prev + 1 => score += CONSECUTIVE_BONUS,
+            Some(prev) => score -= (GAP_PENALTY * (idx - prev - 1) as i64).min(MAX_GAP_PENALTY),
+            None => {}
+        }
+
+        // Reward matches at a word boundary or the very start of the title.
+        if idx == 0 {
+            score += START_BONUS + BOUNDARY_BONUS;
+        } else if matches!(title[idx - 1], ' ' | '_' | '-') {
+            score += BOUNDARY_BONUS;
+        }
+
+        prev_match = Some(idx);
+        cursor = idx + 1;
+    }
+
+    Some(score)
+}
+
+/// Levenshtein edit distance between `a` and `b`, counting single-character
+/// insertions, deletions and substitutions. Iteration is over Unicode scalar
+/// values, not bytes, so CJK terms are compared character-by-character
+/// rather than byte-by-byte. Used by the indexer's "did you mean" term
+/// lookup (`crate::indexer::Indexer::search`) to find the closest indexed
+/// term to a query token that isn't itself indexed.
+pub fn levenshtein_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, &ac) in a.iter().enumerate() {
+        let mut prev_diag = row[0];
+        row[0] = i + 1;
+        for (j, &bc) in b.iter().enumerate() {
+            let temp = row[j + 1];
+            row[j + 1] = if ac == bc {
+                prev_diag
+            } else {
+                1 + prev_diag.min(row[j]).min(row[j + 1])
+            };
+            prev_diag = temp;
+        }
+    }
+
+    row[b.len()]
+}
+
+/// Offset applied to channel/supergroup IDs in the bot-API "marked" form.
+const CHANNEL_ID_OFFSET: i64 = 1_000_000_000_000;
+
+/// Kind of Telegram peer a chat ID refers to.
+///
+/// The deep link `https://t.me/c/{id}/{msg}` is only valid for channels and
+/// supergroups; users and basic groups need a different (or no) link form.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PeerType {
+    /// A user (or bot) — a non-negative marked id.
+    User,
+    /// A basic (legacy) group chat.
+    Chat,
+    /// A channel or supergroup.
+    Channel,
+}
+
+/// Resolve a bot-API "marked" chat id into its share id and [`PeerType`].
+///
+/// Ports Telethon's `resolve_id` semantics:
+/// - a non-negative id is a [`PeerType::User`] with that id;
+/// - otherwise the id is negated; if the result exceeds
+///   [`CHANNEL_ID_OFFSET`] the offset is subtracted and it is a
+///   [`PeerType::Channel`];
+/// - otherwise it is a basic [`PeerType::Chat`].
 ///
 /// Reference: Telethon's resolve_id function
 /// https://github.com/LonamiWebs/Telethon/blob/master/telethon/utils.py
-pub fn get_share_id(chat_id: i64) -> i64 {
-    // Based on Telethon's resolve_id logic:
-    // - Channels/megagroups: -100XXXXXXXXXX -> XXXXXXXXXX
-    // - Other chats: use as-is but ensure positive
-
-    if chat_id < 0 {
-        // Remove the -100 prefix for channels/megagroups
-        let abs_id = chat_id.abs();
-        if abs_id > 1_000_000_000_000 {
-            // It's a channel/megagroup ID (-100XXXXXXXXXX)
-            abs_id - 1_000_000_000_000
-        } else {
-            abs_id
-        }
+pub fn resolve_id(chat_id: i64) -> (i64, PeerType) {
+    if chat_id >= 0 {
+        return (chat_id, PeerType::User);
+    }
+
+    let negated = -chat_id;
+    if negated > CHANNEL_ID_OFFSET {
+        (negated - CHANNEL_ID_OFFSET, PeerType::Channel)
     } else {
-        chat_id
+        (negated, PeerType::Chat)
     }
 }
 
+/// Get the normalized share ID from a Telegram chat ID, discarding the peer
+/// type. Thin wrapper over [`resolve_id`] kept for the many call sites that
+/// only need the numeric id.
+pub fn get_share_id(chat_id: i64) -> i64 {
+    resolve_id(chat_id).0
+}
+
+/// Build a message URL for a chat, given its resolved peer type.
+///
+/// Only channels/supergroups have a valid public `t.me/c/{id}/{msg}` deep
+/// link; for users and basic groups there is no such link, so `None` is
+/// returned and callers should fall back to a non-link representation.
+pub fn build_message_url(peer_type: PeerType, share_id: i64, msg_id: i32) -> Option<String> {
+    match peer_type {
+        PeerType::Channel => Some(format!("https://t.me/c/{}/{}", share_id, msg_id)),
+        PeerType::User | PeerType::Chat => None,
+    }
+}
+
+/// Build the stable identifier [`crate::types::IndexMsg::url`] is keyed by:
+/// the real deep link where [`build_message_url`] has one, or else a
+/// non-clickable placeholder unique per `(share_id, msg_id)` so private
+/// chats and basic groups still dedupe/delete correctly without a dead
+/// `/c/` link being fabricated. Callers surfacing this to the user (e.g. an
+/// "Open" button) should check it starts with `https://` first.
+pub fn build_message_key(peer_type: PeerType, share_id: i64, msg_id: i32) -> String {
+    build_message_url(peer_type, share_id, msg_id)
+        .unwrap_or_else(|| format!("tg-searcher://msg/{}/{}", share_id, msg_id))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -84,6 +240,15 @@ mod tests {
         assert_eq!(remove_first_word("single"), "");
     }
 
+    #[test]
+    fn test_levenshtein_distance() {
+        assert_eq!(levenshtein_distance("kitten", "sitting"), 3);
+        assert_eq!(levenshtein_distance("same", "same"), 0);
+        assert_eq!(levenshtein_distance("", "abc"), 3);
+        // Distance is measured in chars, not bytes.
+        assert_eq!(levenshtein_distance("测试", "测验"), 1);
+    }
+
     #[test]
     fn test_get_share_id() {
         // Channel/megagroup ID
@@ -95,4 +260,59 @@ mod tests {
         // Positive ID
         assert_eq!(get_share_id(123456), 123456);
     }
+
+    #[test]
+    fn test_resolve_id_peer_type() {
+        assert_eq!(resolve_id(123456), (123456, PeerType::User));
+        assert_eq!(resolve_id(-123456), (123456, PeerType::Chat));
+        assert_eq!(resolve_id(-1001234567890), (1234567890, PeerType::Channel));
+    }
+
+    #[test]
+    fn test_fuzzy_match_score() {
+        // Out-of-order multi-term query still matches (word order ignored).
+        assert!(fuzzy_match_score("proj rust", "Rust Project Discussion").is_some());
+
+        // A word-boundary, start-anchored match outranks a scattered one.
+        let boundary = fuzzy_match_score("rust", "Rust Project").unwrap();
+        let scattered = fuzzy_match_score("rust", "Trustworthy Usenet Stories").unwrap();
+        assert!(boundary > scattered);
+
+        // A term whose chars are not all present in order is rejected.
+        assert!(fuzzy_match_score("xyz", "Rust Project").is_none());
+
+        // Whitespace-only queries match nothing.
+        assert!(fuzzy_match_score("   ", "Rust Project").is_none());
+
+        // Scoring iterates over scalar values so CJK titles work.
+        assert!(fuzzy_match_score("工程", "Rust 工程讨论组").is_some());
+    }
+
+    #[test]
+    fn test_build_message_url() {
+        assert_eq!(
+            build_message_url(PeerType::Channel, 1234567890, 42),
+            Some("https://t.me/c/1234567890/42".to_string())
+        );
+        assert_eq!(build_message_url(PeerType::User, 123456, 42), None);
+        assert_eq!(build_message_url(PeerType::Chat, 123456, 42), None);
+    }
+
+    #[test]
+    fn test_build_message_key() {
+        assert_eq!(
+            build_message_key(PeerType::Channel, 1234567890, 42),
+            "https://t.me/c/1234567890/42"
+        );
+        // No real deep link for users/basic groups, but the key is still
+        // unique per (share_id, msg_id) and doesn't look like a t.me URL.
+        assert_eq!(
+            build_message_key(PeerType::User, 123456, 42),
+            "tg-searcher://msg/123456/42"
+        );
+        assert_eq!(
+            build_message_key(PeerType::Chat, 123456, 42),
+            "tg-searcher://msg/123456/42"
+        );
+    }
 }